@@ -1,4 +1,8 @@
 mod sim;
+mod weights;
+
+use std::path::PathBuf;
+use std::time::Duration;
 
 use clap::{Parser, ValueEnum};
 
@@ -21,24 +25,196 @@ pub struct Args {
     
     #[arg(short, long, default_value_t = 8)]
     threads: u32,
+
+    /// Nodes searched per placement, instead of thinking for a piece's worth of time.
+    #[arg(short, long)]
+    nodes: Option<u64>,
+
+    #[arg(short, long, value_enum, default_value_t = Ruleset::Tetrio)]
+    rules: Ruleset,
+
+    /// Seeds pieces and garbage, and the tuner. Random if not given.
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// How pieces are dealt.
+    #[arg(long, value_enum, default_value_t = Randomizer::Bag7)]
+    randomizer: Randomizer,
+
+    /// Threads of the second bot in versus. Defaults to `threads`.
+    #[arg(long)]
+    opponent_threads: Option<u32>,
+
+    /// Evaluation weights (TOML or JSON). Defaults to the built-in set.
+    #[arg(short, long)]
+    weights: Option<PathBuf>,
+
+    /// Evaluation weights of the second bot in versus.
+    #[arg(long)]
+    opponent_weights: Option<PathBuf>,
+
+    /// Selects the nodes the bot expands.
+    #[arg(long, value_enum, default_value_t = Policy::MinExpansions)]
+    policy: Policy,
+
+    /// Policy of the second bot in versus. Defaults to `policy`.
+    #[arg(long, value_enum)]
+    opponent_policy: Option<Policy>,
+
+    /// Prints the line the bot plans before each placement.
+    #[arg(long)]
+    pv: bool,
+
+    /// Writes a replay of the run (sandbox, backfire or versus), to be played back by replay mode.
+    #[arg(long)]
+    record: Option<PathBuf>,
+
+    #[command(flatten)]
+    headless: sim::headless::HeadlessArgs,
+
+    #[command(flatten)]
+    tune: sim::tune::TuneArgs,
+
+    #[command(flatten)]
+    cheese: sim::cheese::CheeseArgs,
+
+    #[command(flatten)]
+    bench: sim::bench::BenchArgs,
+
+    #[command(flatten)]
+    replay: sim::replay::ReplayArgs,
+}
+
+impl Args {
+    /// Creates the bot configured by the arguments.
+    pub fn bot (&self) -> quaternion::Quaternion {
+        let builder = quaternion::Quaternion::builder()
+            .threads(self.threads)
+            .eval(weights::load_or_default(self.weights.as_deref()));
+        self.policy.apply(builder).build()
+    }
+
+    /// Creates a game as seeded by the arguments.
+    pub fn game (&self) -> quaternion::SimState {
+        quaternion::SimState::seeded(self.randomizer.randomizer(), self.seed())
+            .with_rules(self.rules.rules())
+    }
+
+    /// Search per placement.
+    pub fn budget (&self) -> sim::Budget {
+        match self.nodes {
+            Some(nodes) => sim::Budget::Nodes(nodes),
+            None => sim::Budget::Time(Duration::from_secs_f32(1.0 / self.pps)),
+        }
+    }
+
+    pub fn seed (&self) -> u64 {
+        self.seed.expect("seed is chosen in main")
+    }
+
+    /// Creates the second bot in versus.
+    pub fn opponent (&self) -> quaternion::Quaternion {
+        let builder = quaternion::Quaternion::builder()
+            .threads(self.opponent_threads.unwrap_or(self.threads))
+            .eval(weights::load_or_default(self.opponent_weights.as_deref()));
+        self.opponent_policy.unwrap_or(self.policy).apply(builder).build()
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 pub enum Mode {
     Sandbox,
-    Backfire
-//    Cheese,
+    Backfire,
+    Versus,
+    Tune,
+    Replay,
+    Headless,
+    Cheese,
+    Bench,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Ruleset {
+    Tetrio,
+    TetrioS2,
+    Jstris,
+}
+
+impl Ruleset {
+    pub fn rules (self) -> quaternion::Rules {
+        let attack = match self {
+            Ruleset::Tetrio   => quaternion::AttackRules::Tetrio,
+            Ruleset::TetrioS2 => quaternion::AttackRules::TetrioS2,
+            Ruleset::Jstris   => quaternion::AttackRules::Jstris,
+        };
+        // TETR.IO S2 scores non-T spins as minis.
+        let spins = match self {
+            Ruleset::TetrioS2 => quaternion::SpinRules::AllMini,
+            _ => quaternion::SpinRules::TOnly,
+        };
+        // Both TETR.IO seasons have 180 rotations.
+        let flip = matches!(self, Ruleset::Tetrio | Ruleset::TetrioS2);
+        quaternion::Rules { attack, spins, flip }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Randomizer {
+    Bag7,
+    Bag14,
+    Classic,
+    /// 7-bag shuffled after TETR.IO's generator. Not checked against recorded games.
+    Tetrio,
+    /// 7-bag drawn after Jstris's generator, the seed read in base 36. Not checked against recorded games.
+    Jstris,
+}
+
+impl Randomizer {
+    pub fn randomizer (self) -> quaternion::Randomizer {
+        match self {
+            Randomizer::Bag7    => quaternion::Randomizer::Bag7,
+            Randomizer::Bag14   => quaternion::Randomizer::Bag14,
+            Randomizer::Classic => quaternion::Randomizer::Classic,
+            Randomizer::Tetrio  => quaternion::Randomizer::Tetrio,
+            Randomizer::Jstris  => quaternion::Randomizer::Jstris,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Policy {
+    /// The child selected the least.
+    MinExpansions,
+    /// UCT over evaluations normalized among siblings.
+    Uct,
+    /// Random, weighted by rank.
+    Rank,
+}
+
+impl Policy {
+    pub fn apply (self, builder: quaternion::Builder) -> quaternion::Builder {
+        match self {
+            Policy::MinExpansions => builder.policy(quaternion::MinExpansions),
+            Policy::Uct           => builder.policy(quaternion::Uct::default()),
+            Policy::Rank          => builder.policy(quaternion::Rank::default()),
+        }
+    }
 }
 
 fn main() {
-    let args = Args::parse();
+    let mut args = Args::parse();
+    args.seed.get_or_insert_with(rand::random);
     
     println!("arguments: {:?}\n", args);
 
     match args.mode {
         Mode::Sandbox  => sim::sandbox::run(args),
         Mode::Backfire => sim::backfire::run(args),
-//        Mode::Cheese   => sim::cheese::run(args),
-        _ => println!("Not yet implemented")
+        Mode::Versus   => sim::versus::run(args),
+        Mode::Tune     => sim::tune::run(args),
+        Mode::Replay   => sim::replay::run(args),
+        Mode::Headless => sim::headless::run(args),
+        Mode::Cheese   => sim::cheese::run(args),
+        Mode::Bench    => sim::bench::run(args),
     }
 }
@@ -1,5 +1,39 @@
 pub mod stats;
 pub mod sandbox;
-// pub mod cheese;
-// pub mod bencher;
+pub mod cheese;
 pub mod backfire;
+pub mod versus;
+pub mod tune;
+pub mod replay;
+pub mod headless;
+pub mod bench;
+
+use std::time::Duration;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use quaternion::{Move, Quaternion, QuaternionError};
+
+/// Garbage holes of a player, seeded apart from the pieces.
+pub fn garbage_rng (seed: u64, player: u64) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(1 + player);
+    rng
+}
+
+/// Search per placement.
+#[derive(Clone, Copy, Debug)]
+pub enum Budget {
+    Time (Duration),
+    Nodes (u64),
+}
+
+impl Budget {
+    pub fn think (self, bot: &Quaternion) -> Result<Move, QuaternionError> {
+        match self {
+            Budget::Time(duration) => bot.think_for(duration),
+            Budget::Nodes(nodes)   => bot.think_nodes(nodes),
+        }
+    }
+}
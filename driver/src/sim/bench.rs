@@ -0,0 +1,295 @@
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use quaternion::{bench, EvalConfig, State, Worker};
+use crate::*;
+
+
+/*
+ * Throughput of movegen, evaluation and the search, on a fixed corpus of states.
+ * Each measurement runs for `--bench-ms`, cycling through the corpus. The report is written as
+ * JSON, and compared against a previous one if given.
+ */
+
+#[derive(clap::Args, Debug)]
+pub struct BenchArgs {
+    /// Milliseconds each measurement runs for.
+    #[arg(long, default_value_t = 1000)]
+    bench_ms: u64,
+
+    /// Where to write the JSON report.
+    #[arg(long)]
+    report: Option<PathBuf>,
+
+    /// A previous report to compare against.
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+}
+
+/// Bump when measurements change meaning, so old reports are not compared against new ones.
+const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Report {
+    version: u32,
+    corpus: usize,
+    bench_ms: u64,
+    micro: Vec<Micro>,
+    work: Vec<Work>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Micro {
+    name: String,
+    /// Items produced per second.
+    per_sec: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Work {
+    threads: u32,
+    nodes_per_sec: f64,
+    cycles_per_sec: f64,
+}
+
+const CORPUS: [&str; 4] = [
+// Opener
+"
+. . . . . . . . . .  b2b:    0
+. . . . . . . . . .  combo:  0
+. . . . . . . . . .
+. . . . . . . . . .  hold:  none
+. . . . . . . . . .  queue:
+. . . . . . . . . .  T
+. . . . . . . . . .  I
+. . . . . . . . . .  O
+. . . . . . . . . .  L
+. . . . . . . . . .  J
+. . . . . . . . . .
+. . . . . . . . . .
+. . . . . . . . . .
+. . . . . . . . . .
+. . . . . . . . . .
+. . . . . . . . . .
+. . . . . . . . . .
+. . . . . . . . . .
+. . . . . . . . . .
+. . . . . . . . . .
+",
+// T-spin double setup
+"
+. . . . . . . . . .  b2b:    1
+. . . . . . . . . .  combo:  0
+. . . . . . . . . .
+. . . . . . . . . .  hold:  I
+. . . . . . . . . .  queue:
+. . . . . . . . . .  T
+. . . . . . . . . .  S
+. . . . . . . . . .  Z
+. . . . . . . . . .  O
+. . . . . . . . . .  L
+. . . . . . . . . .
+. . . . . . . . . .
+. . . . . . . . . .
+. . . . . . . . . .
+. . . . . . . . . .
+. . . . . . . . . .
+# # . . . . . . . .
+# # # . . . # # # .
+# # # # . # # # # .
+# # # # # # # # # .
+",
+// Cheese
+"
+. . . . . . . . . .  b2b:    0
+. . . . . . . . . .  combo:  0
+. . . . . . . . . .
+. . . . . . . . . .  hold:  none
+. . . . . . . . . .  queue:
+. . . . . . . . . .  J
+. . . . . . . . . .  S
+. . . . . . . . . .  I
+. . . . . . . . . .  T
+. . . . . . . . . .  Z
+. . . . . . . . . .
+. . . . . . . . . .
+. . . . . . . . . .
+. # . . . . . . # .
+# # # # . # # # # #
+# # # # # # # . # #
+# . # # # # # # # #
+# # # # # # . # # #
+# # # . # # # # # #
+# # # # # # # # . #
+",
+// High, uneven stack
+"
+. . . . . . . . . .  b2b:    0
+. . . . . . . . . .  combo:  2
+. . . . . . . . . .
+. . . . . . . . . .  hold:  T
+. . . . . . . . . .  queue:
+. . . . . . . . . .  O
+. . . . . . . . . .  L
+. . . . . . . . . .  I
+. . . . . . . . . .  Z
+# . . . . . . . . .  S
+# # . . . . . . . .
+# # . . . . . # . .
+# # # . . . # # . .
+# # # . . # # # # .
+# # # # . # # # # .
+# # # # # # # # # .
+# # . # # # # # # .
+# # # # # # # . # .
+# # # # # . # # # #
+# # # # # # # # . #
+",
+];
+
+
+pub fn run (args: crate::Args) {
+    let corpus: Vec<State> = CORPUS.iter().map(|s| State::from_str(s)).collect();
+    let config = weights::load_or_default(args.weights.as_deref());
+    let duration = Duration::from_millis(args.bench.bench_ms);
+
+    println!("{BLD}=== Bench Run ==={RST}");
+    println!("corpus:  {} states", corpus.len());
+    println!("time:    {:?} per measurement", duration);
+    println!("threads: up to {}", args.threads);
+
+    let report = Report {
+        version: VERSION,
+        corpus: corpus.len(),
+        bench_ms: args.bench.bench_ms,
+        micro: micro(&corpus, &config, duration),
+        work: thread_counts(args.threads)
+            .into_iter()
+            .map(|threads| work(&corpus, threads, duration))
+            .collect(),
+    };
+
+    let baseline = args.bench.baseline.as_deref().map(load);
+    print(&report, baseline.as_ref());
+
+    if let Some(path) = &args.bench.report {
+        let json = serde_json::to_string_pretty(&report).expect("could not serialize report");
+        std::fs::write(path, json)
+            .unwrap_or_else(|e| panic!("could not write report {}: {e}", path.display()));
+        println!("report written to {}", path.display());
+    }
+}
+
+/// Powers of two up to `max`, and `max` itself.
+fn thread_counts (max: u32) -> Vec<u32> {
+    let mut counts: Vec<u32> = (0..32).map(|i| 1 << i).take_while(|n| *n < max).collect();
+    counts.push(max.max(1));
+    counts
+}
+
+/// Runs `f` over the corpus, round-robin, until the time is up. `f` returns the items it produced.
+/// Returns items per second.
+fn measure<T> (corpus: &[T], duration: Duration, mut f: impl FnMut(&T) -> usize) -> f64 {
+    let start = Instant::now();
+    let mut items = 0;
+    for item in corpus.iter().cycle() {
+        items += f(item);
+        if start.elapsed() >= duration {
+            break;
+        }
+    }
+    items as f64 / start.elapsed().as_secs_f64()
+}
+
+fn micro (corpus: &[State], config: &EvalConfig, duration: Duration) -> Vec<Micro> {
+    // Placements to evaluate, made ahead so only the evaluation is timed.
+    let placements: Vec<_> = corpus.iter()
+        .flat_map(|state| state.legal_moves().into_iter().map(move |mov| state.clone().apply_move_with_stats(&mov)))
+        .collect();
+
+    let micro = |name: &str, per_sec| Micro { name: name.to_owned(), per_sec };
+    vec![
+        micro("gen_moves (moves/s)", measure(corpus, duration, |state| bench::gen_moves(state).len())),
+        micro("gen_children (children/s)", measure(corpus, duration, |state| bench::gen_children(state, config))),
+        micro("evaluate (evals/s)", measure(&placements, duration, |(state, stats)| {
+            std::hint::black_box(bench::evaluate(state, stats.clone(), config));
+            1
+        })),
+    ]
+}
+
+/// Expands a fresh tree of each corpus state, with `threads` threads calling `Worker::work`.
+fn work (corpus: &[State], threads: u32, duration: Duration) -> Work {
+    let per_state = duration / corpus.len() as u32;
+    let mut nodes = 0;
+    let mut cycles = 0;
+    let mut elapsed = Duration::ZERO;
+
+    for state in corpus {
+        let worker = Worker::new();
+        worker.advance(state).expect("corpus state has no pieces");
+        worker.start(&mut worker.state.lock()).expect("worker is already running");
+
+        let start = Instant::now();
+        cycles += thread::scope(|s| {
+            let handles: Vec<_> = (0..threads)
+                .map(|_| s.spawn(|| {
+                    let mut cycles = 0;
+                    while start.elapsed() < per_state && worker.state.lock().should_work() {
+                        worker.work();
+                        cycles += 1;
+                    }
+                    cycles
+                }))
+                .collect();
+            handles.into_iter().map(|h| h.join().expect("bench thread panicked")).sum::<u64>()
+        });
+        elapsed += start.elapsed();
+        nodes += worker.stats().nodes;
+    }
+
+    Work {
+        threads,
+        nodes_per_sec: nodes as f64 / elapsed.as_secs_f64(),
+        cycles_per_sec: cycles as f64 / elapsed.as_secs_f64(),
+    }
+}
+
+fn load (path: &Path) -> Report {
+    let text = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("could not read baseline {}: {e}", path.display()));
+    serde_json::from_str(&text)
+        .unwrap_or_else(|e| panic!("could not parse baseline {}: {e}", path.display()))
+}
+
+fn print (report: &Report, baseline: Option<&Report>) {
+    let baseline = baseline.filter(|baseline| {
+        let comparable = baseline.version == report.version && baseline.corpus == report.corpus;
+        if !comparable {
+            println!("baseline was made by another version of the bench, not comparing");
+        }
+        comparable
+    });
+    // Change against the baseline, in percent.
+    let change = |now: f64, then: Option<f64>| match then {
+        Some(then) => format!("{:+.1}%", (now / then - 1.0) * 100.0),
+        None => String::new(),
+    };
+
+    println!("{BLD} == Bench == {RST}");
+    for micro in &report.micro {
+        let then = baseline
+            .and_then(|b| b.micro.iter().find(|m| m.name == micro.name))
+            .map(|m| m.per_sec);
+        println!("{:<26}: {:>12.0} {}", micro.name, micro.per_sec, change(micro.per_sec, then));
+    }
+    for work in &report.work {
+        let then = baseline
+            .and_then(|b| b.work.iter().find(|w| w.threads == work.threads))
+            .map(|w| w.nodes_per_sec);
+        println!("work x{:<3} (nodes/s)       : {:>12.0} {}", work.threads, work.nodes_per_sec, change(work.nodes_per_sec, then));
+    }
+}
@@ -1,70 +1,97 @@
-use std::time::Duration;
-use std::thread;
+use std::time::Instant;
 
-use rand::prelude::*;
-use rand_chacha::ChaCha8Rng;
-
-use quaternion::game;
-use super::state;
 use super::stats::Stats;
 use crate::*;
 
 
+/*
+ * Cheese race. The board starts with messy garbage, which is topped back up whenever a
+ * placement does not clear, until `iters` lines of it have been dug out. Measures downstacking.
+ */
+
+#[derive(clap::Args, Debug)]
+pub struct CheeseArgs {
+    /// Cheese rows kept on the board, until the last of the `iters` lines are out. At most the
+    /// height of the visible field.
+    #[arg(long, default_value_t = 10)]
+    cheese_rows: u32,
+}
+
+
 pub fn run (args: crate::Args) {
+    let lines = args.iters;
+    let height = args.cheese.cheese_rows.min(lines).min(quaternion::VISIBLE_HEIGHT as u32);
 
-    println!("{BLD}=== Sandbox Run ==={RST}");
+    println!("{BLD}=== Cheese Run ==={RST}");
     println!("threads: {}", args.threads);
-    println!("iters:   {}", args.iters);
+    println!("lines:   {}", args.iters);
+    println!("rows:    {}", height);
     println!("pps:     {}", args.pps);
+    println!("budget:  {:?}", args.budget());
+    println!("rules:   {:?}", args.rules);
+    println!("weights: {:?}", args.weights);
+    println!("policy:  {:?}", args.policy);
+    println!("seed:    {} ({:?})", args.seed(), args.randomizer);
 
     let mut stats = Stats::new();
-    let mut state = state::State::new(game::State::new());
-    let mut rng   = ChaCha8Rng::seed_from_u64(2);
-    let bot       = quaternion::Quaternion::with_threads(args.threads);
-    
-    let mut cheese_clears = 0;
-    let mut cheese_rows = 0;
-    let cheese_lines = args.iters;
-    while cheese_rows < 10.min(cheese_lines) {
-        state.gen_garbage(&mut rng, 1);
-        cheese_rows += 1;
-    }   
-
-    state.draw(&mut rng);
+    let mut state = args.game();
+    let mut rng   = super::garbage_rng(args.seed(), 0);
+    let bot       = args.bot();
 
-    println!("init state:\n{}", state);
-    bot.advance(state.get_state().clone());
-    bot.start();
+    // Cheese rows on the board. Always the bottom ones.
+    let mut rows = height;
+    let mut cleared = 0;
+    let mut pieces = 0;
+    state.gen_cheese(rows as usize, &mut rng);
 
-    while cheese_lines > cheese_clears {
-        thread::sleep(Duration::from_millis((1000.0 / args.pps) as u64));
+    println!("init state:\n{}", state);
+    bot.advance(state.get_state()).expect("state has no pieces");
+    bot.start().expect("bot is already running");
+    let start = Instant::now();
 
-        let (mv, game_state) = bot.solution();
+    while cleared < lines {
+        let mov = match args.budget().think(&bot) {
+            Ok(mov) => mov,
+            Err(e) => {
+                println!("{BLD}game over{RST}: {e}");
+                break;
+            }
+        };
         let bot_stats = bot.stats();
-        state.advance(&mv, &game_state);
-        state.draw(&mut rng);
+        let (n_state, move_stats) = state.advance(&mov);
+        stats.accumulate(&move_stats, &bot_stats);
+        state = n_state;
+        pieces += 1;
 
         // Count & make cheese
-        for y in (20-cheese_rows)..20 {
-            if game_state.props.clears & 1 << y > 0 {
-                cheese_rows -= 1;
-                cheese_clears += 1;
-            }
-        }
-    
-        if game_state.props.clears == 0 {
-           while cheese_rows + cheese_clears < cheese_lines && cheese_rows < 10 {
-                state.gen_garbage(&mut rng, 1);
-                cheese_rows += 1;
-            }
+        let mask = 1u64.checked_shl(rows).map_or(u64::MAX, |bit| bit - 1);
+        let dug = (state.cleared() & mask).count_ones();
+        rows -= dug;
+        cleared += dug;
+        if move_stats.ds == 0 {
+            let refill = height.min(lines - cleared).saturating_sub(rows);
+            state.gen_cheese(refill as usize, &mut rng);
+            rows += refill;
         }
 
-        bot.advance(state.get_state().clone());
+        // Advance
+        bot.advance(state.get_state()).expect("state has no pieces");
 
-        stats.accumulate(&game_state, &bot_stats);
-        println!("{}", state);
+        // Refresh bag
+        state.draw();
+
+        // Render
+        println!("{state}");
+        println!("{:?}", move_stats);
+        println!("cheese: {cleared}/{lines}");
     }
-    bot.stop();
+    let seconds = start.elapsed().as_secs_f32();
+    bot.stop().expect("bot is not running");
+
     println!("{}", stats);
+    println!("{BLD} == Cheese == {RST}");
+    println!("cleared  : {cleared}/{lines}");
+    println!("pieces   : {pieces}");
+    println!("pieces/l : {:.3}", pieces as f32 / cleared.max(1) as f32);
+    println!("seconds  : {:.1}", seconds);
 }
-
@@ -0,0 +1,194 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Instant;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use quaternion::{EvalConfig, Quaternion, SimState};
+use super::versus;
+use crate::*;
+
+
+/*
+ * Games played without rendering, by single-threaded bots, several at once. Time is counted in
+ * pieces: each placement searches for its budget, and the game moves on at once.
+ */
+
+#[derive(clap::Args, Debug)]
+pub struct HeadlessArgs {
+    /// Game played by headless and tune.
+    #[arg(long, value_enum, default_value_t = Objective::Backfire)]
+    pub objective: Objective,
+
+    /// Games played by headless, and by each candidate per generation by tune. At least 1.
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..))]
+    pub games: u32,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+pub enum Objective {
+    /// Attack per piece, alone.
+    Sandbox,
+    /// Attack per piece, receiving 75% of it back.
+    Backfire,
+    /// Win rate against the opponent (the `--weights` set, when tuning).
+    Versus,
+}
+
+/// How a game went, for the bot played.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Outcome {
+    /// Placements made.
+    pub pieces: u32,
+    pub attack: u32,
+    /// Nodes searched over the game.
+    pub nodes: u64,
+    pub topped_out: bool,
+    /// Versus only. 1 for a win, 0 for a loss, 0.5 for a draw.
+    pub result: f32,
+}
+
+
+pub fn run (args: crate::Args) {
+    let config = weights::load_or_default(args.weights.as_deref());
+    let opponent = weights::load_or_default(args.opponent_weights.as_deref());
+    let objective = args.headless.objective;
+
+    println!("{BLD}=== Headless Run ==={RST}");
+    println!("objective: {:?}", objective);
+    println!("games:     {} ({} at a time)", args.headless.games, args.threads.max(1));
+    println!("iters:     {}", args.iters);
+    println!("budget:    {:?}", args.budget());
+    println!("rules:     {:?}", args.rules);
+    println!("weights:   {:?}", args.weights);
+    println!("policy:    {:?}", args.policy);
+    println!("seed:      {} ({:?})", args.seed(), args.randomizer);
+
+    let start = Instant::now();
+    let seeds: Vec<u64> = (0..args.headless.games as u64).map(|game| args.seed().wrapping_add(game)).collect();
+    let outcomes = run_all(&seeds, args.threads, |&seed| {
+        let outcome = play(&config, &opponent, objective, seed, &args);
+        println!("seed {seed}: {} pieces, {} attack{}",
+            outcome.pieces,
+            outcome.attack,
+            if outcome.topped_out { ", topped out" } else { "" });
+        outcome
+    });
+    let seconds = start.elapsed().as_secs_f32();
+
+    let pieces: u32 = outcomes.iter().map(|o| o.pieces).sum();
+    let attack: u32 = outcomes.iter().map(|o| o.attack).sum();
+    let nodes: u64 = outcomes.iter().map(|o| o.nodes).sum();
+    let app: Vec<f32> = outcomes.iter().map(|o| o.attack as f32 / o.pieces.max(1) as f32).collect();
+    let mean = app.iter().sum::<f32>() / app.len() as f32;
+    let deviation = (app.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / app.len() as f32).sqrt();
+
+    println!("{BLD} == Result == {RST}");
+    println!("games      : {}", outcomes.len());
+    println!("pieces     : {}", pieces);
+    println!("topped out : {}", outcomes.iter().filter(|o| o.topped_out).count());
+    println!("app        : {:.3} (game sd {:.3})", attack as f32 / pieces.max(1) as f32, deviation);
+    println!("nodes/i    : {:.0}", nodes as f64 / pieces.max(1) as f64);
+    println!("pieces/s   : {:.1}", pieces as f32 / seconds);
+    if objective == Objective::Versus {
+        let result: f32 = outcomes.iter().map(|o| o.result).sum();
+        println!("win rate   : {:.3}", result / outcomes.len() as f32);
+    }
+}
+
+/// Runs `f` over the jobs, `threads` at a time. Outcomes are in the order of the jobs.
+pub fn run_all<J, T, F> (jobs: &[J], threads: u32, f: F) -> Vec<T>
+where
+    J: Sync,
+    T: Send,
+    F: Fn(&J) -> T + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let outcomes = Mutex::new((0..jobs.len()).map(|_| None).collect::<Vec<_>>());
+
+    thread::scope(|s| {
+        for _ in 0..threads.max(1) {
+            s.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(job) = jobs.get(i) else { break };
+                let outcome = f(job);
+                outcomes.lock().unwrap()[i] = Some(outcome);
+            });
+        }
+    });
+
+    outcomes.into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|outcome| outcome.expect("job was not run"))
+        .collect()
+}
+
+/// A single-threaded bot. Games are parallelized instead.
+pub fn bot (config: &EvalConfig, policy: crate::Policy) -> Quaternion {
+    let builder = Quaternion::builder().threads(0).eval(config.clone());
+    policy.apply(builder).build()
+}
+
+/// Plays one game of `config`. In versus, against `opponent`.
+pub fn play (config: &EvalConfig, opponent: &EvalConfig, objective: Objective, seed: u64, args: &crate::Args) -> Outcome {
+    let budget = args.budget();
+    let game = || SimState::seeded(args.randomizer.randomizer(), seed).with_rules(args.rules.rules());
+
+    match objective {
+        Objective::Sandbox | Objective::Backfire => {
+            let bot = bot(config, args.policy);
+            let mut state = game();
+            let mut rng = super::garbage_rng(seed, 0);
+            let mut outcome = Outcome::default();
+
+            bot.advance(state.get_state()).expect("state has no pieces");
+            for _ in 0..args.iters {
+                if state.get_state().legal_moves().is_empty() {
+                    outcome.topped_out = true;
+                    break;
+                }
+                let Ok(mov) = budget.think(&bot) else {
+                    outcome.topped_out = true;
+                    break
+                };
+                outcome.nodes += bot.stats().nodes;
+                let (n_state, stats) = state.advance(&mov);
+                state = n_state;
+                outcome.attack += stats.attacks as u32;
+                outcome.pieces += 1;
+
+                if objective == Objective::Backfire {
+                    let backfire = (stats.attacks - stats.cancelled) as f32 * 0.75;
+                    state.queue_garbage(backfire as usize, 0, &mut rng);
+                }
+                state.draw();
+                bot.advance(state.get_state()).expect("state has no pieces");
+            }
+            // Never started if topped out before the first placement.
+            let _ = bot.stop();
+
+            outcome
+        },
+        Objective::Versus => {
+            let policy = args.opponent_policy.unwrap_or(args.policy);
+            let mut a = versus::Player::new("bot", bot(config, args.policy), game(), 0);
+            let mut b = versus::Player::new("opponent", bot(opponent, policy), game(), 1);
+            let losers = versus::play(&mut a, &mut b, args.iters, budget, false);
+
+            Outcome {
+                pieces: a.pieces,
+                attack: a.attack,
+                nodes: a.nodes,
+                topped_out: losers.contains(&"bot"),
+                result: match losers.as_slice() {
+                    ["opponent"] => 1.0,
+                    ["bot"] => 0.0,
+                    _ => 0.5,
+                },
+            }
+        },
+    }
+}
@@ -0,0 +1,160 @@
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use quaternion::{BotStats, Garbage, Move, MoveStats, Randomizer, SimState, State};
+use crate::*;
+
+
+/*
+ * Replays of sandbox, backfire and versus runs.
+ * Each placement is stored with the state it led to, so the pieces drawn and garbage received
+ * need not be re-generated. Playback re-applies the moves onto a `SimState` to color the board,
+ * taking the rest of the state from the file.
+ */
+
+#[derive(clap::Args, Debug)]
+pub struct ReplayArgs {
+    /// Replay to play back in replay mode.
+    #[arg(long)]
+    replay: Option<PathBuf>,
+
+    /// Frame to start the playback from.
+    #[arg(long, default_value_t = 0)]
+    frame: usize,
+
+    /// Waits for input between frames. Enter steps, `b` steps back, a number seeks, `q` quits.
+    #[arg(long)]
+    step: bool,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Replay {
+    pub players: Vec<Track>,
+}
+
+/// One player's game.
+#[derive(Serialize, Deserialize)]
+pub struct Track {
+    pub name: String,
+    /// Seed of the pieces and garbage.
+    pub seed: u64,
+    pub randomizer: Randomizer,
+    pub initial: State,
+    pub frames: Vec<Frame>,
+}
+
+/// A placement.
+#[derive(Serialize, Deserialize)]
+pub struct Frame {
+    pub mov: Move,
+    pub stats: MoveStats,
+    /// Garbage queued onto the player after the placement.
+    pub garbage: Vec<Garbage>,
+    /// Search stats of the bot when it gave the move.
+    pub bot: BotStats,
+    /// State after the placement, garbage and new pieces.
+    pub state: State,
+}
+
+impl Replay {
+    pub fn load (path: &Path) -> Self {
+        let text = std::fs::read_to_string(path)
+            .unwrap_or_else(|e| panic!("could not read replay {}: {e}", path.display()));
+        serde_json::from_str(&text)
+            .unwrap_or_else(|e| panic!("could not parse replay {}: {e}", path.display()))
+    }
+
+    pub fn save (&self, path: &Path) {
+        let json = serde_json::to_string(self).expect("could not serialize replay");
+        std::fs::write(path, json)
+            .unwrap_or_else(|e| panic!("could not write replay {}: {e}", path.display()));
+        println!("replay written to {}", path.display());
+    }
+
+    /// Writes the replay if a path is given.
+    pub fn save_to (&self, path: Option<&Path>) {
+        if let Some(path) = path {
+            self.save(path);
+        }
+    }
+}
+
+impl Track {
+    pub fn new (name: &str, game: &SimState) -> Self {
+        Self {
+            name: name.to_owned(),
+            seed: game.seed(),
+            randomizer: game.randomizer(),
+            initial: game.get_state().clone(),
+            frames: vec![],
+        }
+    }
+
+    /// Renders the game after every frame, the initial state first.
+    fn render (&self) -> Vec<String> {
+        let mut state = SimState::from_state(self.initial.clone());
+        let mut out = vec![format!("{state}")];
+        for frame in &self.frames {
+            state = state.advance(&frame.mov).0;
+            state.state = frame.state.clone();
+
+            let mut text = format!("{state}{:?}\n", frame.stats);
+            if !frame.garbage.is_empty() {
+                let lines: u32 = frame.garbage.iter().map(|g| g.lines as u32).sum();
+                text += &format!("received: {lines}\n");
+            }
+            text += &format!("nodes: {}", frame.bot.nodes);
+            out.push(text);
+        }
+        out
+    }
+}
+
+
+pub fn run (args: crate::Args) {
+    let Some(path) = args.replay.replay.as_deref() else {
+        println!("replay mode needs a file: --replay <path>");
+        return;
+    };
+    let replay = Replay::load(path);
+    let tracks: Vec<Vec<String>> = replay.players.iter().map(Track::render).collect();
+    let len = tracks.iter().map(|frames| frames.len()).max().unwrap_or(0);
+    if len == 0 {
+        println!("replay has no players");
+        return;
+    }
+
+    let show = |i: usize| {
+        println!("{BLD}frame {i}/{}{RST}", len - 1);
+        for (track, frames) in replay.players.iter().zip(&tracks) {
+            // A player that topped out stays on its last frame.
+            let frame = &frames[i.min(frames.len() - 1)];
+            println!("{BLD}{}{RST}\n{frame}\n", track.name);
+        }
+    };
+
+    let mut i = args.replay.frame.min(len - 1);
+    if !args.replay.step {
+        for i in i..len {
+            show(i);
+        }
+        return;
+    }
+
+    let mut lines = std::io::stdin().lock().lines();
+    loop {
+        show(i);
+        let Some(Ok(line)) = lines.next() else { break };
+        match line.trim() {
+            "" => i = (i + 1).min(len - 1),
+            "b" => i = i.saturating_sub(1),
+            "q" => break,
+            n => match n.parse::<usize>() {
+                Ok(n) => i = n.min(len - 1),
+                Err(_) => println!("enter: next, b: back, <n>: seek, q: quit"),
+            },
+        }
+    }
+}
@@ -1,9 +1,4 @@
-use std::time::Duration;
-use std::thread;
-
-use rand::prelude::*;
-use rand_chacha::ChaCha8Rng;
-
+use super::replay::{Frame, Replay, Track};
 use super::stats::Stats;
 use crate::*;
 
@@ -14,36 +9,57 @@ pub fn run (args: crate::Args) {
     println!("threads: {}", args.threads);
     println!("iters:   {}", args.iters);
     println!("pps:     {}", args.pps);
+    println!("budget:  {:?}", args.budget());
+    println!("rules:   {:?}", args.rules);
+    println!("weights: {:?}", args.weights);
+    println!("policy:  {:?}", args.policy);
+    println!("seed:    {} ({:?})", args.seed(), args.randomizer);
 
     let mut stats = Stats::new();
-    let mut state = quaternion::SimState::new();
-    //let mut rng   = ChaCha8Rng::seed_from_u64(2);
-    let bot       = quaternion::Quaternion::with_threads(args.threads);
+    let mut state = args.game();
+    let bot       = args.bot();
+    let mut track = Track::new("bot", &state);
     
     println!("init state:\n{}", state);
-    bot.advance(state.get_state());
-    bot.start();
+    bot.advance(state.get_state()).expect("state has no pieces");
+    bot.start().expect("bot is already running");
 
     for _ in 0..args.iters {
-        thread::sleep(Duration::from_millis((1000.0 / args.pps) as u64));
-
-        // Get solution & stats
-        let mov = bot.solution();
+        // Think for a piece's worth of time, get solution & stats
+        let mov = match args.budget().think(&bot) {
+            Ok(mov) => mov,
+            Err(e) => {
+                println!("{BLD}game over{RST}: {e}");
+                break;
+            }
+        };
+        if args.pv {
+            println!("{}", bot.principal_variation());
+        }
+        let bot_stats = bot.stats();
         let (n_state, move_stats) = state.advance(&mov);
-        stats.accumulate(&move_stats, &bot.stats());
+        stats.accumulate(&move_stats, &bot_stats);
         state = n_state;
 
         // Advance
-        bot.advance(state.get_state());
+        bot.advance(state.get_state()).expect("state has no pieces");
 
         // Refresh bag
         state.draw();
+        track.frames.push(Frame {
+            mov,
+            stats: move_stats.clone(),
+            garbage: vec![],
+            bot: bot_stats,
+            state: state.get_state().clone(),
+        });
 
         // Render
         println!("{state}");
         println!("{:?}", move_stats);
     }
-    bot.stop();
+    bot.stop().expect("bot is not running");
     println!("{}", stats);
+    Replay { players: vec![track] }.save_to(args.record.as_deref());
 }
 
@@ -1,4 +1,5 @@
 use std::time::Instant;
+use quaternion::{Piece, Spin};
 use crate::*;
 
 // Tracks: Over time, over iterations
@@ -23,8 +24,12 @@ impl Stats {
             start_time: Instant::now(),
             vals: vec![
                 ("nodes", vec![Move], 0.0),
+                ("merged", vec![Move], 0.0),
                 ("pieces", vec![Second, Sum], 0.0),
-                ("attacks", vec![Minute, Move], 0.0)
+                ("attacks", vec![Minute, Move], 0.0),
+                ("tspins", vec![Minute, Sum], 0.0),
+                ("minis", vec![Sum], 0.0),
+                ("spins", vec![Sum], 0.0),
             ]
         }
     }
@@ -71,8 +76,15 @@ impl Stats {
     pub fn accumulate (&mut self, move_stats: &quaternion::MoveStats, bot_stats: &quaternion::BotStats) {
 
         *self.get("nodes").unwrap() += bot_stats.nodes as f64;
+        *self.get("merged").unwrap() += bot_stats.merged as f64;
         *self.get("pieces").unwrap() += 1.0;
         *self.get("attacks").unwrap() += move_stats.attacks as f64;
+        match (move_stats.spin, move_stats.piece) {
+            (Spin::Full, Piece::T) => *self.get("tspins").unwrap() += 1.0,
+            (Spin::Full, _)        => *self.get("spins").unwrap() += 1.0,
+            (Spin::Mini, _)        => *self.get("minis").unwrap() += 1.0,
+            (Spin::None, _)        => (),
+        }
 
         self.iters += 1;
     }
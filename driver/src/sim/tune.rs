@@ -0,0 +1,250 @@
+use std::path::{Path, PathBuf};
+
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use quaternion::EvalConfig;
+use super::headless::{self, Objective};
+use crate::*;
+
+
+/*
+ * Genetic weight tuner.
+ * Each generation, every candidate plays the same seeded headless games. The fittest survive
+ * as elites, and the rest of the population is bred from them by crossover and mutation.
+ * Only the `Weights` of both modes are tuned; factors and thresholds are carried over as is.
+ */
+
+#[derive(clap::Args, Debug)]
+pub struct TuneArgs {
+    /// Generations to run, including those loaded from the checkpoint.
+    #[arg(long, default_value_t = 20)]
+    generations: u32,
+
+    #[arg(long, default_value_t = 8)]
+    population: usize,
+
+    /// Written every generation, resumed from if it exists. The best weights are written next to
+    /// it, with a `.best.toml` extension.
+    #[arg(long, default_value = "tune.json")]
+    checkpoint: PathBuf,
+}
+
+/// Elites kept unchanged into the next generation.
+const ELITES: usize = 2;
+/// Standard deviation of mutations, relative to the weight (or 1, for weights near 0).
+const MUTATION: f32 = 0.15;
+/// Chance of each weight being mutated.
+const MUTATION_RATE: f64 = 0.3;
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Candidate {
+    config: EvalConfig,
+    fitness: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Checkpoint {
+    objective: Objective,
+    seed: u64,
+    /// Generations completed.
+    generation: u32,
+    population: Vec<Candidate>,
+    best: Option<Candidate>,
+}
+
+impl Checkpoint {
+    fn new (args: &crate::Args, base: &EvalConfig) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(args.seed());
+        let population = (0..args.tune.population)
+            .map(|i| Candidate {
+                config: if i == 0 { base.clone() } else { mutate(base, &mut rng) },
+                fitness: None,
+            })
+            .collect();
+
+        Self {
+            objective: args.headless.objective,
+            seed: args.seed(),
+            generation: 0,
+            population,
+            best: None,
+        }
+    }
+
+    fn load (path: &Path) -> Option<Self> {
+        let text = std::fs::read_to_string(path).ok()?;
+        Some(serde_json::from_str(&text).expect("could not parse checkpoint"))
+    }
+
+    /// Writes through a temporary file, so an interrupted write does not lose the last checkpoint.
+    fn save (&self, path: &Path) {
+        let tmp = path.with_extension("tmp");
+        let json = serde_json::to_string_pretty(self).expect("could not serialize checkpoint");
+        std::fs::write(&tmp, json).expect("could not write checkpoint");
+        std::fs::rename(&tmp, path).expect("could not write checkpoint");
+
+        if let Some(best) = &self.best {
+            let toml = toml::to_string(&best.config).expect("could not serialize weights");
+            std::fs::write(path.with_extension("best.toml"), toml).expect("could not write best weights");
+        }
+    }
+}
+
+
+pub fn run (args: crate::Args) {
+    let base = weights::load_or_default(args.weights.as_deref());
+    let mut checkpoint = match Checkpoint::load(&args.tune.checkpoint) {
+        Some(checkpoint) => {
+            println!("resuming from {:?} at generation {}", args.tune.checkpoint, checkpoint.generation);
+            // The checkpoint decides these, whatever was asked for.
+            if args.headless.objective != checkpoint.objective {
+                println!("warning: --objective {:?} ignored, the checkpoint tunes for {:?}", args.headless.objective, checkpoint.objective);
+            }
+            if args.tune.population != checkpoint.population.len() {
+                println!("warning: --population {} ignored, the checkpoint has {}", args.tune.population, checkpoint.population.len());
+            }
+            checkpoint
+        },
+        None => Checkpoint::new(&args, &base),
+    };
+
+    println!("{BLD}=== Tune Run ==={RST}");
+    println!("objective:  {:?}", checkpoint.objective);
+    println!("population: {}", checkpoint.population.len());
+    println!("games:      {}", args.headless.games);
+    println!("iters:      {}", args.iters);
+    println!("budget:     {:?}", args.budget());
+    println!("threads:    {}", args.threads);
+    println!("rules:      {:?}", args.rules);
+    println!("policy:     {:?}", args.policy);
+
+    while checkpoint.generation < args.tune.generations {
+        let generation = checkpoint.generation;
+        let seed = checkpoint.seed.wrapping_add(generation as u64);
+
+        // Score. Every candidate plays the same seeds, so they are compared on equal footing.
+        let configs: Vec<_> = checkpoint.population.iter().map(|c| c.config.clone()).collect();
+        let fitness = score(&configs, &base, checkpoint.objective, seed, &args);
+        for (candidate, fitness) in checkpoint.population.iter_mut().zip(fitness) {
+            candidate.fitness = Some(fitness);
+        }
+        checkpoint.population.sort_by(|a, b| b.fitness.partial_cmp(&a.fitness).unwrap());
+
+        let top = checkpoint.population[0].clone();
+        if checkpoint.best.as_ref().is_none_or(|best| top.fitness > best.fitness) {
+            checkpoint.best = Some(top);
+        }
+
+        println!("{BLD}generation {}{RST}: best {:.4}, median {:.4}, all-time {:.4}",
+            generation,
+            checkpoint.population[0].fitness.unwrap(),
+            checkpoint.population[checkpoint.population.len() / 2].fitness.unwrap(),
+            checkpoint.best.as_ref().unwrap().fitness.unwrap());
+
+        // Breed
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        checkpoint.population = breed(&checkpoint.population, &mut rng);
+        checkpoint.generation += 1;
+        checkpoint.save(&args.tune.checkpoint);
+    }
+
+    if let Some(best) = &checkpoint.best {
+        println!("{BLD}best{RST} ({:.4}):\n{}", best.fitness.unwrap(), toml::to_string(&best.config).unwrap());
+    }
+}
+
+/// Plays `games` games per config, `threads` at a time. Returns each config's mean fitness.
+fn score (configs: &[EvalConfig], base: &EvalConfig, objective: Objective, seed: u64, args: &crate::Args) -> Vec<f32> {
+    let games = args.headless.games;
+    let jobs: Vec<(usize, u64)> = (0..configs.len())
+        .flat_map(|i| (0..games as u64).map(move |game| (i, seed.wrapping_mul(1000).wrapping_add(game))))
+        .collect();
+    let fitness = headless::run_all(&jobs, args.threads, |&(i, seed)| {
+        let outcome = headless::play(&configs[i], base, objective, seed, args);
+        match objective {
+            // Pieces not played after topping out count as sending nothing.
+            Objective::Sandbox | Objective::Backfire => outcome.attack as f32 / args.iters as f32,
+            Objective::Versus => outcome.result,
+        }
+    });
+
+    fitness
+        .chunks(games.max(1) as usize)
+        .map(|games| games.iter().sum::<f32>() / games.len() as f32)
+        .collect()
+}
+
+/// Keeps the elites, and fills the rest with mutated crossovers of candidates from the top half.
+fn breed (sorted: &[Candidate], rng: &mut ChaCha8Rng) -> Vec<Candidate> {
+    let parents = &sorted[..(sorted.len() / 2).max(1)];
+
+    let mut out: Vec<_> = sorted
+        .iter()
+        .take(ELITES)
+        .cloned()
+        .collect();
+
+    while out.len() < sorted.len() {
+        let a = &parents[rng.gen_range(0..parents.len())].config;
+        let b = &parents[rng.gen_range(0..parents.len())].config;
+        out.push(Candidate {
+            config: mutate(&crossover(a, b, rng), rng),
+            fitness: None,
+        });
+    }
+    out
+}
+
+/*
+ * Weights are handled as a flat list of numbers, through their serialized form, so new fields
+ * are tuned without being listed here.
+ */
+
+const TUNED: [&str; 2] = ["weights_atk", "weights_ds"];
+
+fn params (config: &EvalConfig) -> Vec<f32> {
+    let value = serde_json::to_value(config).unwrap();
+    TUNED
+        .iter()
+        .flat_map(|k| value[*k].as_object().unwrap().values().map(|v| v.as_f64().unwrap() as f32).collect::<Vec<_>>())
+        .collect()
+}
+
+fn with_params (config: &EvalConfig, params: &[f32]) -> EvalConfig {
+    let mut value = serde_json::to_value(config).unwrap();
+    let mut params = params.iter();
+    for k in TUNED {
+        for v in value[k].as_object_mut().unwrap().values_mut() {
+            *v = Value::from(*params.next().unwrap() as f64);
+        }
+    }
+    serde_json::from_value(value).unwrap()
+}
+
+fn mutate (config: &EvalConfig, rng: &mut ChaCha8Rng) -> EvalConfig {
+    let params: Vec<_> = params(config)
+        .into_iter()
+        .map(|p| {
+            if !rng.gen_bool(MUTATION_RATE) {
+                return p;
+            }
+            // Box-Muller: normally distributed noise
+            let (u1, u2): (f32, f32) = (rng.gen_range(f32::EPSILON..1.0), rng.gen());
+            let noise = (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos();
+            p + noise * MUTATION * p.abs().max(1.0)
+        })
+        .collect();
+    with_params(config, &params)
+}
+
+fn crossover (a: &EvalConfig, b: &EvalConfig, rng: &mut ChaCha8Rng) -> EvalConfig {
+    let params: Vec<_> = params(a)
+        .into_iter()
+        .zip(params(b))
+        .map(|(a, b)| if rng.gen() { a } else { b })
+        .collect();
+    with_params(a, &params)
+}
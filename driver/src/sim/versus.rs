@@ -0,0 +1,191 @@
+use std::thread;
+
+use rand_chacha::ChaCha8Rng;
+
+use quaternion::{Quaternion, SimState};
+use super::replay::{Frame, Replay, Track};
+use super::Budget;
+use crate::*;
+
+
+/// Placements garbage waits before entering the board. The receiver gets one piece to cancel it.
+const GARBAGE_DELAY: u8 = 0;
+
+pub struct Player {
+    pub name: &'static str,
+    pub bot: Quaternion,
+    state: SimState,
+    rng: ChaCha8Rng,
+    pub pieces: u32,
+    pub attack: u32,
+    /// Nodes searched over the game.
+    pub nodes: u64,
+    /// Garbage rows on the board. Garbage sits at the bottom, so clears are counted against it
+    /// first when estimating garbage cleared.
+    garbage_rows: u32,
+    garbage_cleared: u32,
+    track: Track,
+}
+
+impl Player {
+    /// `player` tells apart the garbage of players sharing a seed.
+    pub fn new (name: &'static str, bot: Quaternion, state: SimState, player: u64) -> Self {
+        bot.advance(state.get_state()).expect("state has no pieces");
+        let track = Track::new(name, &state);
+        let rng = super::garbage_rng(state.seed(), player);
+
+        Self {
+            name,
+            bot,
+            state,
+            rng,
+            pieces: 0,
+            attack: 0,
+            nodes: 0,
+            garbage_rows: 0,
+            garbage_cleared: 0,
+            track,
+        }
+    }
+
+    /// Whether the player has topped out (block out, or nowhere to place without locking out).
+    fn topped_out (&self) -> bool {
+        self.state.get_state().legal_moves().is_empty()
+    }
+
+    /// Places the move. Returns lines sent to the opponent.
+    fn play (&mut self, mov: &quaternion::Move) -> u8 {
+        let state = std::mem::replace(&mut self.state, SimState::new());
+        let (state, stats) = state.advance(mov);
+        self.state = state;
+        let bot = self.bot.stats();
+        self.nodes += bot.nodes;
+        self.track.frames.push(Frame {
+            mov: mov.clone(),
+            stats: stats.clone(),
+            garbage: vec![],
+            bot,
+            state: self.state.get_state().clone(),
+        });
+
+        let cleared = (stats.ds as u32).min(self.garbage_rows);
+        self.garbage_rows = self.garbage_rows - cleared + stats.received as u32;
+        self.garbage_cleared += cleared;
+        self.attack += stats.attacks as u32;
+        self.pieces += 1;
+
+        stats.attacks - stats.cancelled
+    }
+
+    fn receive (&mut self, lines: u8) {
+        if lines > 0 {
+            let garbage = self.state.queue_garbage(lines as usize, GARBAGE_DELAY, &mut self.rng);
+            if let Some(frame) = self.track.frames.last_mut() {
+                frame.garbage.push(garbage);
+            }
+        }
+    }
+
+    /// Refills the queue, and moves the bot onto the resulting state.
+    fn next (&mut self) {
+        self.state.draw();
+        if let Some(frame) = self.track.frames.last_mut() {
+            frame.state = self.state.get_state().clone();
+        }
+        self.bot.advance(self.state.get_state()).expect("state has no pieces");
+    }
+
+    fn report (&self, pps: f32) {
+        let minutes = self.pieces as f32 / pps / 60.0;
+        let apm = self.attack as f32 / minutes;
+        let app = self.attack as f32 / self.pieces as f32;
+        let vs = (self.attack + self.garbage_cleared) as f32 / (minutes * 60.0) * 100.0;
+
+        println!("{BLD}{}{RST}", self.name);
+        println!("attack  : {}", self.attack);
+        println!("apm     : {:.2}", apm);
+        println!("app     : {:.3}", app);
+        println!("vs      : {:.2}", vs);
+    }
+}
+
+/// Two bots playing each other, exchanging garbage. Both search concurrently, as per the budget,
+/// then place. Time is counted in pieces at `pps`, not wall time.
+pub fn run (args: crate::Args) {
+    let opponent_threads = args.opponent_threads.unwrap_or(args.threads);
+
+    println!("{BLD}=== Versus Run ==={RST}");
+    println!("threads: {} vs {}", args.threads, opponent_threads);
+    println!("iters:   {}", args.iters);
+    println!("pps:     {}", args.pps);
+    println!("budget:  {:?}", args.budget());
+    println!("rules:   {:?}", args.rules);
+    println!("weights: {:?} vs {:?}", args.weights, args.opponent_weights);
+    println!("policy:  {:?} vs {:?}", args.policy, args.opponent_policy.unwrap_or(args.policy));
+    println!("seed:    {} ({:?})", args.seed(), args.randomizer);
+
+    // Both are dealt the same pieces.
+    let mut a = Player::new("bot a", args.bot(), args.game(), 0);
+    let mut b = Player::new("bot b", args.opponent(), args.game(), 1);
+
+    let losers = play(&mut a, &mut b, args.iters, args.budget(), true);
+
+    println!("{BLD} == Result == {RST}");
+    match losers.as_slice() {
+        [loser] => println!("winner  : {}", if *loser == a.name { b.name } else { a.name }),
+        [] => println!("draw: no top out in {} pieces", args.iters),
+        _ => println!("draw: both topped out"),
+    }
+    a.report(args.pps);
+    b.report(args.pps);
+    Replay { players: vec![a.track, b.track] }.save_to(args.record.as_deref());
+}
+
+/// Plays until a player tops out, or for `iters` pieces. Returns the names of those who topped out.
+pub fn play (a: &mut Player, b: &mut Player, iters: u32, budget: Budget, render: bool) -> Vec<&'static str> {
+    a.bot.start().expect("bot a is already running");
+    b.bot.start().expect("bot b is already running");
+
+    let mut losers = vec![];
+    for _ in 0..iters {
+        losers = [&*a, &*b].into_iter().filter(|p| p.topped_out()).map(|p| p.name).collect();
+        if !losers.is_empty() {
+            break;
+        }
+
+        let (mov_a, mov_b) = thread::scope(|s| {
+            let mov_a = s.spawn(|| budget.think(&a.bot));
+            let mov_b = budget.think(&b.bot);
+            (mov_a.join().expect("bot a panicked"), mov_b)
+        });
+        let (mov_a, mov_b) = match (mov_a, mov_b) {
+            (Ok(mov_a), Ok(mov_b)) => (mov_a, mov_b),
+            (mov_a, mov_b) => {
+                // A bot without a move loses, as if it had topped out.
+                losers = [(a.name, mov_a.is_err()), (b.name, mov_b.is_err())]
+                    .into_iter()
+                    .filter(|(_, lost)| *lost)
+                    .map(|(name, _)| name)
+                    .collect();
+                break;
+            }
+        };
+
+        let sent_a = a.play(&mov_a);
+        let sent_b = b.play(&mov_b);
+        a.receive(sent_b);
+        b.receive(sent_a);
+
+        a.next();
+        b.next();
+
+        if render {
+            println!("{BLD}{}{RST}\n{}", a.name, a.state);
+            println!("{BLD}{}{RST}\n{}", b.name, b.state);
+        }
+    }
+    a.bot.stop().expect("bot a is not running");
+    b.bot.stop().expect("bot b is not running");
+
+    losers
+}
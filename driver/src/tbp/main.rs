@@ -0,0 +1,208 @@
+mod message;
+#[path = "../weights.rs"]
+mod weights;
+
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use quaternion::{Move, Piece, Quaternion, Rotation, State};
+
+use message::*;
+
+
+/// Tetris Bot Protocol frontend. Speaks JSON messages over stdin/stdout.
+#[derive(Parser, Debug)]
+pub struct Args {
+    #[arg(short, long, default_value_t = 8)]
+    threads: u32,
+
+    /// Minimum time (ms) spent on a position before answering `suggest`.
+    #[arg(long, default_value_t = 200)]
+    think: u64,
+
+    /// Evaluation weights (TOML or JSON). Defaults to the built-in set.
+    #[arg(short, long)]
+    weights: Option<PathBuf>,
+}
+
+struct Session {
+    bot: Quaternion,
+    state: Option<State>,
+    running: bool,
+    advanced: Instant,
+}
+
+impl Session {
+    fn advance (&mut self, state: State) {
+        if let Err(e) = self.bot.advance(&state) {
+            eprintln!("tbp: could not advance: {e}");
+        }
+        self.state = Some(state);
+        self.advanced = Instant::now();
+    }
+
+    fn suggest (&self, think: Duration) -> Vec<TbpMove> {
+        let Some(state) = &self.state else { return vec![] };
+        if state.legal_moves().is_empty() {
+            return vec![];
+        }
+
+        // Give the workers time on the current position.
+        match self.bot.solution_within(self.advanced + think) {
+            Ok(mov) => to_tbp(state, &mov).into_iter().collect(),
+            Err(e) => {
+                eprintln!("tbp: no suggestion: {e}");
+                vec![]
+            }
+        }
+    }
+
+    fn play (&mut self, mv: &TbpMove) {
+        let Some(state) = &self.state else { return };
+        match find_move(state, mv) {
+            Some(mov) => {
+                let state = state.clone().apply_move(&mov);
+                self.advance(state);
+            },
+            None => eprintln!("tbp: played move is not reachable: {:?}", mv),
+        }
+    }
+}
+
+/// Translates a bot move into TBP's piece location.
+fn to_tbp (state: &State, mov: &Move) -> Option<TbpMove> {
+    let piece = TbpPiece::from_bot(state.placed_piece(mov)?)?;
+    let (_, stats) = state.clone().apply_move_with_stats(mov);
+
+    Some(TbpMove {
+        location: Location {
+            piece,
+            orientation: mov.r().into(),
+            x: mov.x(),
+            y: mov.y(),
+        },
+        spin: stats.spin.into(),
+    })
+}
+
+/// Finds the bot move matching a TBP piece location. TBP moves carry no inputs, so the key list
+/// is taken from movegen, preferring one that agrees with the claimed spin.
+fn find_move (state: &State, mv: &TbpMove) -> Option<Move> {
+    let piece = Piece::from(mv.location.piece);
+    let r = Rotation::from(mv.location.orientation);
+
+    let candidates: Vec<_> = state
+        .legal_moves()
+        .into_iter()
+        .filter(|mov|
+            mov.x() == mv.location.x &&
+            mov.y() == mv.location.y &&
+            mov.r() == r &&
+            state.placed_piece(mov) == Some(piece)
+        )
+        .collect();
+
+    candidates
+        .iter()
+        .find(|mov| Spin::from(state.clone().apply_move_with_stats(mov).1.spin) == mv.spin)
+        .or(candidates.first())
+        .cloned()
+}
+
+fn parse_start (start: Start) -> State {
+    let rows: Vec<[bool; 10]> = start.board
+        .iter()
+        .map(|row| {
+            let mut out = [false; 10];
+            for (x, cell) in row.iter().take(10).enumerate() {
+                out[x] = cell.is_some();
+            }
+            out
+        })
+        .collect();
+
+    State::new(
+        &rows,
+        start.queue.into_iter().map(Piece::from),
+        start.hold.map(Piece::from),
+        start.back_to_back as u8,
+        start.combo.min(u8::MAX as u32) as u8,
+    )
+}
+
+fn send (msg: &BotMessage) {
+    let mut stdout = io::stdout().lock();
+    let json = serde_json::to_string(msg).expect("could not serialize bot message");
+    writeln!(stdout, "{json}").expect("could not write to stdout");
+    stdout.flush().expect("could not flush stdout");
+}
+
+fn main() {
+    let args = Args::parse();
+    let think = Duration::from_millis(args.think);
+
+    let mut session = Session {
+        bot: Quaternion::builder()
+            .threads(args.threads)
+            .eval(weights::load_or_default(args.weights.as_deref()))
+            .build(),
+        state: None,
+        running: false,
+        advanced: Instant::now(),
+    };
+
+    send(&BotMessage::Info {
+        name: "Quaternion",
+        version: env!("CARGO_PKG_VERSION"),
+        author: "Shine Chang",
+        features: vec![],
+    });
+
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let msg: FrontendMessage = match serde_json::from_str(&line) {
+            Ok(msg) => msg,
+            Err(e) => {
+                eprintln!("tbp: could not parse message: {e}");
+                continue;
+            }
+        };
+
+        match msg {
+            FrontendMessage::Rules {} => send(&BotMessage::Ready),
+            FrontendMessage::Start(start) => {
+                session.advance(parse_start(start));
+                if !session.running {
+                    session.bot.start().expect("bot is already running");
+                    session.running = true;
+                }
+            },
+            FrontendMessage::Stop => {
+                if session.running {
+                    session.bot.stop().expect("bot is not running");
+                    session.running = false;
+                }
+                session.state = None;
+            },
+            FrontendMessage::Suggest => {
+                let moves = session.suggest(think);
+                send(&BotMessage::Suggestion { moves });
+            },
+            FrontendMessage::Play { mv } => session.play(&mv),
+            FrontendMessage::NewPiece { piece } => {
+                if let Some(mut state) = session.state.take() {
+                    state.push_piece(piece.into());
+                    session.advance(state);
+                }
+            },
+            FrontendMessage::Quit => break,
+            FrontendMessage::Unknown => (),
+        }
+    }
+}
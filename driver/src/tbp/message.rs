@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+
+use quaternion::{Piece, Rotation};
+use quaternion::Spin as BotSpin;
+
+/*
+ * Tetris Bot Protocol message set.
+ * https://github.com/tetris-bot-protocol/tbp-spec
+ */
+
+/// Messages sent by the frontend.
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FrontendMessage {
+    // Rule fields are optional extensions. The bot plays the same regardless.
+    Rules {},
+    Start(Start),
+    Stop,
+    Suggest,
+    Play {
+        #[serde(rename = "move")]
+        mv: TbpMove,
+    },
+    NewPiece {
+        piece: TbpPiece,
+    },
+    Quit,
+    // Unknown messages must be ignored as per spec.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Start {
+    pub hold: Option<TbpPiece>,
+    pub queue: Vec<TbpPiece>,
+    pub combo: u32,
+    pub back_to_back: bool,
+    /// 40 rows of 10 cells, bottom row first. Cells are `null` or a piece/garbage character.
+    pub board: Vec<Vec<Option<char>>>,
+}
+
+/// Messages sent by the bot.
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BotMessage {
+    Info {
+        name: &'static str,
+        version: &'static str,
+        author: &'static str,
+        features: Vec<&'static str>,
+    },
+    Ready,
+    Suggestion {
+        moves: Vec<TbpMove>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TbpPiece {
+    I, O, T, L, J, S, Z
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Orientation {
+    North, East, South, West
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Spin {
+    None, Mini, Full
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Location {
+    #[serde(rename = "type")]
+    pub piece: TbpPiece,
+    pub orientation: Orientation,
+    pub x: i8,
+    pub y: i8,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct TbpMove {
+    pub location: Location,
+    pub spin: Spin,
+}
+
+
+impl From<TbpPiece> for Piece {
+    fn from (piece: TbpPiece) -> Self {
+        match piece {
+            TbpPiece::I => Piece::I,
+            TbpPiece::O => Piece::O,
+            TbpPiece::T => Piece::T,
+            TbpPiece::L => Piece::L,
+            TbpPiece::J => Piece::J,
+            TbpPiece::S => Piece::S,
+            TbpPiece::Z => Piece::Z,
+        }
+    }
+}
+
+impl TbpPiece {
+    pub fn from_bot (piece: Piece) -> Option<Self> {
+        match piece {
+            Piece::I => Some(TbpPiece::I),
+            Piece::O => Some(TbpPiece::O),
+            Piece::T => Some(TbpPiece::T),
+            Piece::L => Some(TbpPiece::L),
+            Piece::J => Some(TbpPiece::J),
+            Piece::S => Some(TbpPiece::S),
+            Piece::Z => Some(TbpPiece::Z),
+            Piece::None => None,
+        }
+    }
+}
+
+// TBP's piece origins and orientations are SRS's, same as the bot's. No offset needed.
+impl From<Orientation> for Rotation {
+    fn from (orientation: Orientation) -> Self {
+        match orientation {
+            Orientation::North => Rotation::N,
+            Orientation::East  => Rotation::E,
+            Orientation::South => Rotation::S,
+            Orientation::West  => Rotation::W,
+        }
+    }
+}
+
+impl From<Rotation> for Orientation {
+    fn from (r: Rotation) -> Self {
+        match r {
+            Rotation::N => Orientation::North,
+            Rotation::E => Orientation::East,
+            Rotation::S => Orientation::South,
+            Rotation::W => Orientation::West,
+        }
+    }
+}
+
+impl From<BotSpin> for Spin {
+    fn from (spin: BotSpin) -> Self {
+        match spin {
+            BotSpin::None => Spin::None,
+            BotSpin::Mini => Spin::Mini,
+            BotSpin::Full => Spin::Full,
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use std::path::Path;
+
+use quaternion::EvalConfig;
+
+
+/// Loads evaluation parameters from a TOML or JSON file, chosen by extension.
+/// Fields left out keep their defaults. Panics on unreadable or malformed files.
+pub fn load (path: &Path) -> EvalConfig {
+    let text = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("could not read weights {}: {e}", path.display()));
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str(&text)
+            .unwrap_or_else(|e| panic!("could not parse weights {}: {e}", path.display())),
+        _ => toml::from_str(&text)
+            .unwrap_or_else(|e| panic!("could not parse weights {}: {e}", path.display())),
+    }
+}
+
+/// Loads the parameters if a path is given, otherwise the defaults.
+pub fn load_or_default (path: Option<&Path>) -> EvalConfig {
+    path.map(load).unwrap_or_default()
+}
@@ -0,0 +1,27 @@
+use std::process::Command;
+
+/// The report is JSON, with a rate for each measurement, and compares against itself.
+#[test]
+fn report () {
+    let path = std::env::temp_dir().join(format!("quaternion-bench-{}.json", std::process::id()));
+    let bench = |arg: &str| {
+        let output = Command::new(env!("CARGO_BIN_EXE_quaternion-driver"))
+            .args(["bench", "--bench-ms", "50", "--threads", "2"])
+            .args([arg, path.to_str().unwrap()])
+            .output()
+            .expect("could not run bench");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    bench("--report");
+    let report: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(report["micro"].as_array().unwrap().len(), 3);
+    let work = report["work"].as_array().unwrap();
+    assert_eq!(work.iter().map(|w| w["threads"].as_u64().unwrap()).collect::<Vec<_>>(), [1, 2]);
+    assert!(work.iter().all(|w| w["nodes_per_sec"].as_f64().unwrap() > 0.0));
+
+    let compared = bench("--baseline");
+    assert!(compared.contains('%'));
+    std::fs::remove_file(&path).unwrap();
+}
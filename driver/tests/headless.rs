@@ -0,0 +1,26 @@
+use std::process::Command;
+
+/// With a node budget, a seed fully determines the games.
+#[test]
+fn seeded_games_repeat () {
+    let run = || {
+        let output = Command::new(env!("CARGO_BIN_EXE_quaternion-driver"))
+            .args(["headless", "--iters", "20", "--nodes", "2000", "--games", "2", "--threads", "2", "--seed", "11"])
+            .output()
+            .expect("could not run headless");
+        assert!(output.status.success());
+
+        let mut games: Vec<String> = String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .filter(|line| line.starts_with("seed "))
+            .map(str::to_owned)
+            .collect();
+        games.sort();
+        games
+    };
+
+    let games = run();
+    assert_eq!(games.len(), 2);
+    assert_eq!(games, run());
+}
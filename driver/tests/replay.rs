@@ -0,0 +1,36 @@
+use std::process::Command;
+
+use serde_json::Value;
+
+/// Records a short backfire run, then plays it back.
+#[test]
+fn record_and_replay () {
+    let path = std::env::temp_dir().join(format!("quaternion-replay-{}.json", std::process::id()));
+    let driver = env!("CARGO_BIN_EXE_quaternion-driver");
+
+    let status = Command::new(driver)
+        .args(["backfire", "--iters", "4", "--pps", "20", "--threads", "1", "--record"])
+        .arg(&path)
+        .output()
+        .expect("could not run backfire")
+        .status;
+    assert!(status.success());
+
+    let replay: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    let frames = replay["players"][0]["frames"].as_array().unwrap();
+    assert_eq!(frames.len(), 4);
+    assert!(frames[0]["mov"]["keys"].is_array());
+
+    let output = Command::new(driver)
+        .args(["replay", "--frame", "2", "--replay"])
+        .arg(&path)
+        .output()
+        .expect("could not run replay");
+    std::fs::remove_file(&path).unwrap();
+    assert!(output.status.success());
+
+    let output = String::from_utf8(output.stdout).unwrap();
+    assert!(!output.contains("frame 1/4"));
+    assert!(output.contains("frame 2/4"));
+    assert!(output.contains("frame 4/4"));
+}
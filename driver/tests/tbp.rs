@@ -0,0 +1,55 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+
+use serde_json::Value;
+
+/// Scripted TBP session: plays a few suggestions back to the bot over stdin.
+#[test]
+fn tbp_session () {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_quaternion-tbp"))
+        .args(["--threads", "2", "--think", "100"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("could not spawn quaternion-tbp");
+
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap()).lines();
+    let mut recv = || -> Value {
+        let line = stdout.next().expect("bot closed stdout").unwrap();
+        serde_json::from_str(&line).expect("bot sent invalid json")
+    };
+
+    assert_eq!(recv()["type"], "info");
+
+    writeln!(stdin, r#"{{"type":"rules"}}"#).unwrap();
+    assert_eq!(recv()["type"], "ready");
+
+    let board = vec![vec![Value::Null; 10]; 40];
+    let start = serde_json::json!({
+        "type": "start",
+        "hold": null,
+        "queue": ["T", "I", "O", "L", "J", "S"],
+        "combo": 0,
+        "back_to_back": false,
+        "board": board,
+    });
+    writeln!(stdin, "{start}").unwrap();
+
+    for piece in ["Z", "T", "I"] {
+        writeln!(stdin, r#"{{"type":"suggest"}}"#).unwrap();
+        let suggestion = recv();
+        assert_eq!(suggestion["type"], "suggestion");
+
+        let mv = &suggestion["moves"][0];
+        assert!(mv["location"]["x"].is_i64());
+        assert!(mv["location"]["orientation"].is_string());
+
+        writeln!(stdin, "{}", serde_json::json!({ "type": "play", "move": mv })).unwrap();
+        writeln!(stdin, "{}", serde_json::json!({ "type": "new_piece", "piece": piece })).unwrap();
+    }
+
+    writeln!(stdin, r#"{{"type":"stop"}}"#).unwrap();
+    writeln!(stdin, r#"{{"type":"quit"}}"#).unwrap();
+    assert!(child.wait().unwrap().success());
+}
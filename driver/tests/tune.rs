@@ -0,0 +1,33 @@
+use std::process::Command;
+
+fn tune (args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_quaternion-driver"))
+        .args(["tune", "--iters", "5", "--nodes", "200", "--threads", "2", "--seed", "3"])
+        .args(args)
+        .output()
+        .expect("could not run tune")
+}
+
+#[test]
+fn no_games () {
+    assert!(!tune(&["--games", "0"]).status.success());
+}
+
+/// Resuming keeps the checkpoint's objective and population, and says so if others were asked for.
+#[test]
+fn resume_warns () {
+    let path = std::env::temp_dir().join(format!("quaternion-tune-{}.json", std::process::id()));
+    let checkpoint = ["--checkpoint", path.to_str().unwrap(), "--generations", "1", "--games", "1"];
+
+    let first = tune(&[&checkpoint[..], &["--population", "2", "--objective", "sandbox"]].concat());
+    assert!(first.status.success());
+
+    let resumed = tune(&[&checkpoint[..], &["--population", "3", "--objective", "backfire"]].concat());
+    assert!(resumed.status.success());
+    let stdout = String::from_utf8(resumed.stdout).unwrap();
+    assert!(stdout.contains("--objective Backfire ignored"));
+    assert!(stdout.contains("--population 3 ignored"));
+
+    std::fs::remove_file(&path).unwrap();
+    let _ = std::fs::remove_file(path.with_extension("best.toml"));
+}
@@ -0,0 +1,24 @@
+use quaternion::EvalConfig;
+
+/// The shipped weights file must stay in sync with the built-in weights.
+#[test]
+fn default_weights_file () {
+    let text = include_str!("../weights/default.toml");
+    let config: EvalConfig = toml::from_str(text).expect("could not parse default.toml");
+    assert_eq!(config, EvalConfig::default());
+}
+
+/// Fields left out keep their defaults.
+#[test]
+fn partial_weights () {
+    let config: EvalConfig = serde_json::from_str(r#"{ "ds_height_threshold": 6.0 }"#).unwrap();
+    assert_eq!(config.ds_height_threshold, 6.0);
+    assert_eq!(config.weights_atk, EvalConfig::default().weights_atk);
+
+    // In nested tables too, from the built-in values of that table.
+    let config: EvalConfig = toml::from_str("[weights_atk]\nhole = -100.0\n\n[factors_ds]\nwell_threshold = 5.0\n").unwrap();
+    let default = EvalConfig::default();
+    assert_eq!(config.weights_atk, quaternion::Weights { hole: -100.0, ..default.weights_atk });
+    assert_eq!(config.factors_ds, quaternion::Factors { well_threshold: 5.0, ..default.factors_ds });
+    assert_eq!(config.weights_ds, default.weights_ds);
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use quaternion::{bench, EvalConfig, MinExpansions, Piece, State, Worker};
+
+
+/*
+ * The functions behind the nodes per iteration figure. Boards are fixtures in `State::from_str`'s
+ * format, under `benches/fixtures`.
+ */
+
+const FIXTURES: [(&str, &str); 3] = [
+    ("flat", include_str!("fixtures/flat.txt")),
+    ("messy", include_str!("fixtures/messy.txt")),
+    ("tall", include_str!("fixtures/tall.txt")),
+];
+
+const PIECES: [Piece; 7] = [Piece::J, Piece::L, Piece::S, Piece::Z, Piece::T, Piece::I, Piece::O];
+
+/// Expansions per iteration of the tree benchmark, split between the threads.
+const EXPANSIONS: u32 = 64;
+
+fn fixtures () -> impl Iterator<Item = (&'static str, State)> {
+    FIXTURES.into_iter().map(|(name, s)| (name, State::from_str(s)))
+}
+
+fn movegen (c: &mut Criterion) {
+    let mut group = c.benchmark_group("gen_moves");
+    for (name, state) in fixtures() {
+        group.bench_with_input(BenchmarkId::from_parameter(name), &state, |b, state| {
+            b.iter(|| bench::gen_moves(state))
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("conflict_table");
+    group.throughput(Throughput::Elements(PIECES.len() as u64));
+    for (name, state) in fixtures() {
+        group.bench_with_input(BenchmarkId::from_parameter(name), &state, |b, state| {
+            b.iter(|| for piece in PIECES {
+                bench::conflict_table(state, piece)
+            })
+        });
+    }
+    group.finish();
+}
+
+fn advance (c: &mut Criterion) {
+    let state = State::from_str(include_str!("fixtures/clears.txt"));
+    c.bench_function("clear", |b| b.iter(|| bench::clear(black_box(&state))));
+}
+
+fn eval (c: &mut Criterion) {
+    let config = EvalConfig::default();
+
+    let mut group = c.benchmark_group("tspins");
+    for (name, state) in fixtures() {
+        group.bench_with_input(BenchmarkId::from_parameter(name), &state, |b, state| {
+            b.iter(|| bench::tspins(state))
+        });
+    }
+    group.finish();
+
+    // Every placement of the fixture, evaluated in turn.
+    let mut group = c.benchmark_group("evaluate");
+    for (name, state) in fixtures() {
+        let placements: Vec<_> = state.legal_moves()
+            .into_iter()
+            .map(|mov| state.clone().apply_move_with_stats(&mov))
+            .collect();
+        group.throughput(Throughput::Elements(placements.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &placements, |b, placements| {
+            b.iter(|| {
+                for (state, stats) in placements {
+                    black_box(bench::evaluate(state, stats.clone(), &config));
+                }
+            })
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("gen_children");
+    for (name, state) in fixtures() {
+        group.bench_with_input(BenchmarkId::from_parameter(name), &state, |b, state| {
+            b.iter(|| bench::gen_children(state, &config))
+        });
+    }
+    group.finish();
+}
+
+/// Selections and expansions of a fresh tree, from several threads at once. Shows the
+/// contention on the tree.
+fn tree (c: &mut Criterion) {
+    let state = State::from_str(FIXTURES[1].1);
+    let mut group = c.benchmark_group("select_expand");
+    group.throughput(Throughput::Elements(EXPANSIONS as u64));
+
+    for threads in [1, 2, 4] {
+        group.bench_with_input(BenchmarkId::from_parameter(threads), &threads, |b, &threads| {
+            b.iter_custom(|iters| {
+                let mut total = Duration::ZERO;
+                for _ in 0..iters {
+                    let worker = Worker::with_config(EvalConfig::default(), 1 << 16, Arc::new(MinExpansions));
+                    worker.advance(&state).expect("fixture has no pieces");
+                    worker.start(&mut worker.state.lock()).expect("worker is already running");
+                    let left = AtomicU32::new(EXPANSIONS);
+
+                    let start = Instant::now();
+                    thread::scope(|s| {
+                        for _ in 0..threads {
+                            s.spawn(|| {
+                                while left.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1)).is_ok() {
+                                    worker.work();
+                                }
+                            });
+                        }
+                    });
+                    total += start.elapsed();
+                }
+                total
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, movegen, advance, eval, tree);
+criterion_main!(benches);
@@ -0,0 +1,25 @@
+use crate::game::{self, eval};
+use crate::{EvalConfig, Move, MoveStats, State};
+
+pub use crate::game::bench::{conflict_table, clear, tspins};
+
+
+/*
+ * Entry points into the search, for benchmarks: the driver's bench mode and the criterion benches.
+ * Not a stable interface.
+ */
+
+/// Every placement from the state, hold included.
+pub fn gen_moves (state: &State) -> Vec<Move> {
+    game::movegen::gen_moves(state)
+}
+
+/// Generates and evaluates the children of a state, as an expansion does. Returns how many.
+pub fn gen_children (state: &State, config: &EvalConfig) -> usize {
+    crate::tree::gen_children(state, config).len()
+}
+
+/// Evaluates a placement, as `gen_children` does.
+pub fn evaluate (state: &State, stats: MoveStats, config: &EvalConfig) -> f32 {
+    eval::evaluate(state, stats, eval::Mode::Norm, config)
+}
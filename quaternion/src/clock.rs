@@ -0,0 +1,22 @@
+/// Milliseconds elapsed since a fixed, arbitrary point.
+/// `std::time::Instant` panics on wasm32-unknown-unknown, thus the split.
+#[cfg(not(target_family = "wasm"))]
+pub fn now () -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    EPOCH.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+/// Milliseconds elapsed since a fixed, arbitrary point. Backed by `performance.now()`.
+/// Fetched from the global scope since web workers do not have a `window`.
+#[cfg(target_family = "wasm")]
+pub fn now () -> f64 {
+    use wasm_bindgen::JsCast;
+
+    js_sys::Reflect::get(&js_sys::global(), &"performance".into())
+        .expect("performance should be available")
+        .unchecked_into::<web_sys::Performance>()
+        .now()
+}
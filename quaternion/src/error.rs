@@ -0,0 +1,72 @@
+use std::fmt;
+
+use crate::Rotation;
+
+
+/// Errors of the public interface.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum QuaternionError {
+    /// No legal placement from the current state: the bot has topped out.
+    ToppedOut,
+    /// The current state has not been expanded yet, so there is no move to give. Search longer.
+    NoSolution,
+    /// The state has no pieces to place.
+    EmptyQueue,
+    /// `start()` on a bot already running.
+    Running,
+    /// `stop()` on a bot not running.
+    Stopped,
+    /// A move's input list holds an encoding that is no key.
+    InvalidKey (u8),
+}
+
+impl fmt::Display for QuaternionError {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ToppedOut       => write!(f, "no legal placement, topped out"),
+            Self::NoSolution      => write!(f, "state not searched yet"),
+            Self::EmptyQueue      => write!(f, "no pieces in queue"),
+            Self::Running         => write!(f, "bot is already running"),
+            Self::Stopped         => write!(f, "bot is not running"),
+            Self::InvalidKey(key) => write!(f, "no such key encoding: {key}"),
+        }
+    }
+}
+
+impl std::error::Error for QuaternionError {}
+
+
+/// Why a move's key list does not land it where it claims. See `State::validate_move`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReplayError {
+    /// The key list holds an encoding that is no key.
+    InvalidKey (u8),
+    /// Hold anywhere but first.
+    MisplacedHold,
+    /// A 180 rotation, under rules without them.
+    Flip,
+    /// Nothing to place: the queue is empty, or there is nothing to hold.
+    NoPiece,
+    /// The piece overlaps the stack at spawn.
+    BlockOut,
+    /// The piece lands entirely above the visible field.
+    LockOut,
+    /// The keys land the piece elsewhere. Holds where.
+    Mismatch { x: i8, y: i8, r: Rotation },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidKey(key)     => write!(f, "no such key encoding: {key}"),
+            Self::MisplacedHold       => write!(f, "hold is not the first key"),
+            Self::Flip                => write!(f, "180 rotations are not allowed"),
+            Self::NoPiece             => write!(f, "no piece to place"),
+            Self::BlockOut            => write!(f, "piece cannot spawn"),
+            Self::LockOut             => write!(f, "piece lands above the visible field"),
+            Self::Mismatch { x, y, r } => write!(f, "keys land the piece at ({x}, {y}, {r:?})"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
@@ -1,8 +1,20 @@
 use std::collections::VecDeque;
 
+use crate::{QuaternionError, ReplayError};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 pub mod movegen;
 pub mod advance;
 pub mod eval;
+pub mod rules;
+pub(crate) mod bench;
+
+pub use rules::{Rules, AttackRules, SpinRules};
+
+#[cfg(feature = "serde")]
+mod repr;
 
 #[cfg(not(target_family = "wasm"))]
 pub mod sim;
@@ -14,18 +26,33 @@ pub mod sim;
  */
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Key {
-    L, R, CW, CCW, Drop, Hold
+    L, R, CW, CCW, Drop, Hold, Flip
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Piece {
-    L, J, S, Z, T, I, O, None
+    L, J, S, Z, T, I, O,
+    #[default]
+    None
 }
 
 
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// Spin classification of a placement.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Spin {
+    #[default]
+    None,
+    Mini,
+    Full
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Rotation {
     N, S, E, W
 }
@@ -38,25 +65,53 @@ pub struct Move {
     y: i8,
     r: Rotation,
     list: u64,
+    /// Index of the kick used by the last rotation. Needed for spin detection.
+    kick: u8,
+}
+
+
+/// Garbage sent by an opponent that has not entered the board yet.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Garbage {
+    pub lines: u8,
+    /// Column of the hole.
+    pub col: u8,
+    /// Placements it waits out before it can enter. Placements cancel before garbage enters, so
+    /// it can be cancelled by the next `delay + 1` placements, the last of which lets it in unless
+    /// it clears. Past that, the first placement that does not clear lets it in.
+    pub delay: u8,
 }
 
 
 /// Move's metadata. describes statistics of a move after it is applied onto a state.
 /// used for evaluation and simulation.
-#[derive(Default, Debug)]
+#[derive(Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct MoveStats {
     pub attacks: u8,
     pub ds: u8,
-    pub tspin: bool
+    pub spin: Spin,
+    pub piece: Piece,
+    /// Lines of the attack spent cancelling pending garbage. Included in `attacks`.
+    pub cancelled: u8,
+    /// Lines of garbage that entered the board.
+    pub received: u8,
 }
 
 impl Default for Move {
     fn default() -> Self {
-        Self { x: 0, y: 0, r: Rotation::N, list: 0 }
+        Self { x: 0, y: 0, r: Rotation::N, list: 0, kick: 0 }
     }
 }
 
 
+impl Move {
+    pub fn x (&self) -> i8 { self.x }
+    pub fn y (&self) -> i8 { self.y }
+    pub fn r (&self) -> Rotation { self.r }
+}
+
 impl Move {
     const W: u64 = 3;
     const LEN_W: u64 = 5;
@@ -68,54 +123,60 @@ impl Move {
     }
 
     /// Parses bitset list.
-    pub fn parse_list (&self) -> Vec<Key> {
+    pub fn parse_list (&self) -> Result<Vec<Key>, QuaternionError> {
         (0..self.list_len()).map(|i| {
             let shifts = i * Self::W + Self::LEN_W;
             let mask = Self::MASK << shifts;
             let val  = (self.list & mask) >> shifts;
-            let key  = match val {
-                1 => Key::L,
-                2 => Key::R,
-                3 => Key::CW,
-                4 => Key::CCW,
-                5 => Key::Drop,
-                6 => Key::Hold,
-                _ => panic!("none such key encoding")
-            };
-            key
+            match val {
+                1 => Ok(Key::L),
+                2 => Ok(Key::R),
+                3 => Ok(Key::CW),
+                4 => Ok(Key::CCW),
+                5 => Ok(Key::Drop),
+                6 => Ok(Key::Hold),
+                7 => Ok(Key::Flip),
+                _ => Err(QuaternionError::InvalidKey(val as u8)),
+            }
         }).collect()
     }
+
+    /// Last key of the list. None if empty or unparsable.
+    pub(crate) fn last_key (&self) -> Option<Key> {
+        self.parse_list().ok()?.last().copied()
+    }
 }
 
 
-#[derive(Clone, Debug, Default, PartialEq)]
+/// Rows in the board, including the buffer zone above the visible field.
+pub const BOARD_HEIGHT: usize = 40;
+/// Rows of the visible field. Pieces spawn right above it.
+pub const VISIBLE_HEIGHT: usize = 20;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 struct Board {
-    v: [u32; 10]
+    v: [u64; 10]
 }
 impl Board {
     /// Creates board given the text output of a state or board.
+    /// The last row read is the bottom row. Accepts up to `BOARD_HEIGHT` rows.
     pub fn from_str (s: &str) -> Self {
-        let mut b = [[false; 10]; 20];
-        let mut y = 0;
+        let mut rows: Vec<[bool; 10]> = vec![];
         let mut x = 0;
         for ch in s.chars() {
             if ch == '#' || ch == '.' {
-                b[y][x] = ch == '#'; 
-                x += 1;
-                if x == 10 {
-                    x = 0;
-                    y += 1;
-                }
-                if y == 20 {
-                    break;
+                if x == 0 {
+                    rows.push([false; 10]);
                 }
+                rows.last_mut().unwrap()[x] = ch == '#';
+                x = (x + 1) % 10;
             }
         }
 
         let mut v = [0; 10];
-        for x in 0..10 {
-            for y in 0..20 {
-                if b[19-y][x] {
+        for (y, row) in rows.iter().rev().take(BOARD_HEIGHT).enumerate() {
+            for x in 0..10 {
+                if row[x] {
                     v[x] |= 1 << y;
                 }
             }
@@ -123,10 +184,16 @@ impl Board {
 
         Board { v }
     }
+
+    /// Number of rows that need to be shown: the visible field, plus any occupied buffer rows.
+    fn display_height (&self) -> usize {
+        let h = self.v.iter().map(|col| 64 - col.leading_zeros() as usize).max().unwrap_or(0);
+        h.max(VISIBLE_HEIGHT)
+    }
 }
 impl std::fmt::Display for Board {
     fn fmt (&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        for y in (0..20).rev() {
+        for y in (0..self.display_height()).rev() {
             for x in 0..10 {
                 let b = (self.v[x] & (1 << y)) != 0;
                 write!(f, "{} ", if b { '#' } else { '.' })?;
@@ -139,13 +206,15 @@ impl std::fmt::Display for Board {
 
 
 
-#[derive(Clone, Default, PartialEq)]
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
 pub struct State {
     board: Board,
     queue: VecDeque<Piece>,
     hold: Option<Piece>,
     b2b: u8,
     combo: u8,
+    rules: Rules,
+    garbage: VecDeque<Garbage>,
 }
 impl State {
     // For WASM driver
@@ -153,9 +222,9 @@ impl State {
         let board = {
             let mut v = [0; 10];
             for x in 0..10 {
-                for y in 0..20 {
+                for y in 0..VISIBLE_HEIGHT {
                     if board[y][x] {
-                        v[x] += 1 << (19-y);
+                        v[x] += 1 << (VISIBLE_HEIGHT-1-y);
                     }
                 }
             }
@@ -168,9 +237,82 @@ impl State {
             hold,
             b2b: 0,
             combo: 0,
+            rules: Rules::default(),
+            garbage: VecDeque::new(),
         }
     }
 
+    /// Creates a state from rows of cells, bottom row first. Rows above the board are ignored.
+    /// Used by frontends that hand over their own game state (e.g. TBP).
+    pub fn new (rows: &[[bool; 10]], queue: impl IntoIterator<Item = Piece>, hold: Option<Piece>, b2b: u8, combo: u8) -> Self {
+        let mut v = [0; 10];
+        for (y, row) in rows.iter().take(BOARD_HEIGHT).enumerate() {
+            for x in 0..10 {
+                if row[x] {
+                    v[x] |= 1 << y;
+                }
+            }
+        }
+
+        State {
+            board: Board { v },
+            queue: queue.into_iter().collect(),
+            hold,
+            b2b,
+            combo,
+            rules: Rules::default(),
+            garbage: VecDeque::new(),
+        }
+    }
+
+    /// Sets the rules this state, and all states following it, are played under.
+    pub fn with_rules (mut self, rules: Rules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    pub fn rules (&self) -> Rules {
+        self.rules
+    }
+
+    /// Queues incoming garbage behind any already pending.
+    pub fn push_garbage (&mut self, garbage: Garbage) {
+        if garbage.lines > 0 {
+            self.garbage.push_back(garbage);
+        }
+    }
+
+    /// Pending garbage, oldest first.
+    pub fn garbage (&self) -> impl Iterator<Item = &Garbage> {
+        self.garbage.iter()
+    }
+
+    /// Total lines of pending garbage.
+    pub fn incoming (&self) -> u32 {
+        self.garbage.iter().map(|g| g.lines as u32).sum()
+    }
+
+    /// Hash of everything that determines the game from here on. Used to merge transpositions.
+    pub fn key (&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Determines if self is `prev` with more pieces revealed at the end of the queue.
+    /// Used by the tree to keep its search when only new previews arrive.
+    pub fn extends (&self, prev: &State) -> bool {
+        self.board == prev.board &&
+        self.hold == prev.hold &&
+        self.b2b == prev.b2b &&
+        self.combo == prev.combo &&
+        self.rules == prev.rules &&
+        self.garbage == prev.garbage &&
+        self.queue.len() >= prev.queue.len() &&
+        self.queue.iter().zip(prev.queue.iter()).all(|(a, b)| a == b)
+    }
+
     /// For testing. Creates a State object given the textual formatting of a state.
     pub fn from_str (s: &str) -> Self {
         let s = s.trim();
@@ -233,14 +375,17 @@ impl State {
             hold,
             b2b,
             combo,
+            rules: Rules::default(),
+            garbage: VecDeque::new(),
         }
     }
 }
 impl std::fmt::Display for State {
     fn fmt (&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        for y in 0..20 {
+        let height = self.board.display_height();
+        for y in 0..height {
             for x in 0..10 {
-                let b = (self.board.v[x] & (1 << (19-y))) != 0;
+                let b = (self.board.v[x] & (1 << (height-1-y))) != 0;
                 write!(f, "{} ", if b { '#' } else { '.' })?;
             }
             write!(f, " ")?;
@@ -253,6 +398,7 @@ impl std::fmt::Display for State {
                 5..=9 => if self.queue.len() > y-5 {
                     write!(f, "{:?}", self.queue[y-5])?
                 },
+                11 if !self.garbage.is_empty() => write!(f, "garbage: {}", self.incoming())?,
                 _ => ()
             };
             write!(f, "\n")?;
@@ -2,8 +2,11 @@
 mod tests;
 
 use super::*;
-use crate::tree::Evaluation;
+use super::rules::Clear;
+use super::movegen::ConflictTable;
 
+/// Index of the (±1, ∓2) kick. Spins using it are full T-spins regardless of corners.
+pub(super) const TST_KICK: u8 = 4;
 
 impl Move {
 
@@ -26,13 +29,20 @@ impl Board {
         for (dx, dy) in piece.cells(mov.r) {
             let nx = mov.x + dx;
             let ny = mov.y + dy;
-            assert!(nx >= 0 && nx < 10 && ny >= 0);
+            assert!(nx >= 0 && nx < 10 && ny >= 0 && (ny as usize) < BOARD_HEIGHT);
             assert!(self.v[nx as usize] & (1 << ny) == 0);
             self.v[nx as usize] |= 1 << ny;
         }
     }
 
-    fn clear (&mut self) -> u32 {
+    /// Removes a placed piece from map.
+    fn remove (&mut self, piece: Piece, mov: &Move) {
+        for (dx, dy) in piece.cells(mov.r) {
+            self.v[(mov.x + dx) as usize] &= !(1 << (mov.y + dy));
+        }
+    }
+
+    pub(super) fn clear (&mut self) -> u32 {
 
         // make mask
         let mut mask = self.v.iter()
@@ -57,19 +67,77 @@ impl Board {
         clears
     }
 
-    // Determines if a move is a tspin by 3-corner rule. Assumes the piece is T.
-    // Precondition: The move is not clear()'ed yet
-    fn is_tspin (&self, mov: &Move) -> bool {
+    /// Pushes garbage up from the bottom, with a hole at `col`. Blocks pushed past the buffer zone are lost.
+    pub(super) fn add_garbage (&mut self, lines: u8, col: u8) {
+        let lines = lines.min(BOARD_HEIGHT as u8) as u32;
+        let fill = (1 << lines) - 1;
+        for (x, v) in self.v.iter_mut().enumerate() {
+            *v <<= lines;
+            if x != col as usize {
+                *v |= fill;
+            }
+            *v &= (1 << BOARD_HEIGHT) - 1;
+        }
+    }
+
+    /// Classifies a T placement by the 3-corner rule. The last input must be a rotation.
+    /// Full if both corners the T points towards are filled, or if the TST kick was used. Mini otherwise.
+    /// Assumes the piece is T.
+    /// Precondition: The move is not clear()'ed yet
+    fn tspin (&self, mov: &Move) -> Spin {
+        let last = mov.last_key();
+        if !matches!(last, Some(Key::CCW | Key::CW | Key::Flip)) {
+            return Spin::None;
+        }
+
         let x = mov.x as i32;
         let y = mov.y as i32;
-        let corners = 
-            if self.occupied(x -1, y -1) { 1 } else { 0 } +
-            if self.occupied(x -1, y +1) { 1 } else { 0 } +
-            if self.occupied(x +1, y +1) { 1 } else { 0 } +
-            if self.occupied(x +1, y -1) { 1 } else { 0 };
+        let occupied = |(dx, dy): &(i32, i32)| self.occupied(x + dx, y + dy);
+
+        let corners = [(-1, -1), (-1, 1), (1, 1), (1, -1)]
+            .iter()
+            .filter(|c| occupied(c))
+            .count();
+        if corners < 3 {
+            return Spin::None;
+        }
 
-        // three corners + last input was rotation
-        corners >= 3 && matches!(mov.parse_list().last().unwrap(), Key::CCW | Key::CW)
+        let front = match mov.r {
+            Rotation::N => [(-1,  1), ( 1,  1)],
+            Rotation::E => [( 1,  1), ( 1, -1)],
+            Rotation::S => [(-1, -1), ( 1, -1)],
+            Rotation::W => [(-1,  1), (-1, -1)],
+        };
+
+        // 180 kicks are indexed differently. None of them upgrade.
+        let tst_kick = mov.kick == TST_KICK && last != Some(Key::Flip);
+
+        if front.iter().all(occupied) || tst_kick {
+            Spin::Full
+        } else {
+            Spin::Mini
+        }
+    }
+
+    /// Classifies a non-T placement. Spins if the last input was a rotation and the piece can not
+    /// move up, left or right. Scored as per the spin rules.
+    /// Precondition: The move is not clear()'ed yet
+    fn immobile_spin (&self, piece: Piece, mov: &Move, rules: SpinRules) -> Spin {
+        let kind = match rules {
+            SpinRules::TOnly   => return Spin::None,
+            SpinRules::AllMini => Spin::Mini,
+            SpinRules::AllSpin => Spin::Full,
+        };
+        if !matches!(mov.last_key(), Some(Key::CCW | Key::CW | Key::Flip)) {
+            return Spin::None;
+        }
+
+        let conflict_table = ConflictTable::from(self, piece);
+        let immobile = [(0, 1), (-1, 0), (1, 0)]
+            .iter()
+            .all(|(dx, dy)| conflict_table.conflicts(&Move { x: mov.x + dx, y: mov.y + dy, ..*mov }));
+
+        if immobile { kind } else { Spin::None }
     }
 
     pub fn occupied (&self, x: i32, y: i32) -> bool {
@@ -83,13 +151,12 @@ impl Board {
 
 
 
-use crate::tree::Node;
 impl State {
     
     /// Applies move onto state, returning the resultant child AND THE LINES CLEARED.
     /// Wrapped by `apply-move(..)` for exported interface.
-    /// Used by `make_node(..)` to help calculate attack
-    fn apply_move_return_clears (mut self, mov: &Move) -> (Self, u32, bool) {
+    /// Used by `apply_move_with_stats(..)` to help calculate attack
+    fn apply_move_return_clears (mut self, mov: &Move) -> (Self, u32, Spin, Piece) {
 
         // Retains the piece placed. Needed for t-spin detection
         let placed = if mov.held() {
@@ -113,136 +180,130 @@ impl State {
             piece
         };
 
-        // Check if is tspin. This must be done before 'clear()' is called.
-        let is_tspin = placed == Piece::T && self.board.is_tspin(mov);
+        // Check if is spin. This must be done before 'clear()' is called.
+        // The piece is already placed, thus removed from the board for the immobility check.
+        let spin = if placed == Piece::T { 
+            self.board.tspin(mov) 
+        } else if self.rules.spins != SpinRules::TOnly {
+            let mut board = self.board.clone();
+            board.remove(placed, mov);
+            board.immobile_spin(placed, mov, self.rules.spins)
+        } else { 
+            Spin::None 
+        };
 
-        // Clear & update combo & b2b
+        // Clear & update combo & b2b. B2B is kept through placements that do not clear.
         let clears = self.board.clear();
         if clears > 0 {
-            self.combo += 1;
-            if is_tspin || clears == 4 { self.b2b += 1 } 
+            self.combo = self.combo.saturating_add(1);
+            if Clear::is_difficult(clears, spin) { self.b2b = self.b2b.saturating_add(1) } 
             else { self.b2b = 0 }
         } else {
             self.combo = 0;
-            self.b2b = 0;
         }
 
-        (self, clears, is_tspin)
+        (self, clears, spin, placed)
     }
 
 
     /// Creates node that will be created from self and the input move.
     /// Applies Move, Calculate evaluation `MetaData` (atk, ds, etc), then evaluate.
     pub fn apply_move_with_stats (mut self, mov: &Move) -> (Self, MoveStats) {
+        let prev_b2b = self.b2b;
 
-        let (clears, was_tspin) = {
-            let (n_state, clears, is_tspin) = self.clone().apply_move_return_clears(&mov);
-            // if is_tspin {
-            //     println!("==tspin found");
-            //     println!("{self}");
-            //     println!("{n_state}");
-            // }
-
+        let (clears, spin, piece) = {
+            let (n_state, clears, spin, piece) = self.clone().apply_move_return_clears(&mov);
             self = n_state;
-            (clears, is_tspin)
+            (clears, spin, piece)
         };
 
         // === Calculate Evaluation MetaData ===
-        // case: no clears
-        let mut attacks = if clears == 0 { 
-            0 
-        // case: basic clears 
-        } else if !was_tspin && clears != 4 {
-            match clears {
-                0 => 0,
-                1 => [0, 0, 1, 1, 1, 1, 2, 2, 2, 2][self.combo as usize],
-                2 => [1, 1, 1, 1, 2, 2, 2, 2, 3, 3][self.combo as usize],
-                3 => [2, 2, 3, 3, 4, 4, 5, 5, 6, 6][self.combo as usize],
-                _ => 0
-            }
-        // case: tspin or tetris
-        } else  {
-            // TODO: Attack Table
-            /*
-            let index = if clears == 4 { 0 } else { clears };
-            B2B_TABLE[self.b2b as usize][index as usize][self.combo as usize] as u8
-            */
-            match clears {
-                1 => 2,
-                2 => 4,
-                3 => 6,
-                4 => 4,
-                _ => unreachable!()
-            }
+        let clear = Clear {
+            lines: clears,
+            spin,
+            b2b: self.b2b,
+            prev_b2b,
+            combo: self.combo,
+            all_clear: clears > 0 && self.board.v.iter().all(|col| *col == 0),
         };
-
-        // Perfect clear
-        if clears > 0 && self.board.v.iter().all(|col| *col == 0) {
-            attacks += 10;
-        }
+        let attacks = self.rules.attack.attack(&clear);
+        let (cancelled, received) = self.update_garbage(attacks, clears);
 
         let stats = MoveStats {
             attacks,
             ds: clears as u8,
-            tspin: self.b2b != 0,
+            spin,
+            piece,
+            cancelled,
+            received,
         };
 
         (self, stats)
     }
 
 
-    pub fn make_node (mut self, mov: Move, eval_mode: eval::Mode) -> Node {
-        let (nstate, stats) = self.apply_move_with_stats(&mov);
-        self = nstate;
-
-        // Evaluate
-        let eval = Evaluation::new(eval::evaluate(&self, stats, eval_mode));
+    /// Cancels pending garbage with the attack, oldest first. Garbage that is due enters the board
+    /// if the placement did not clear, and the rest is brought one placement closer.
+    /// Returns lines cancelled and lines received.
+    fn update_garbage (&mut self, attacks: u8, clears: u32) -> (u8, u8) {
+        let mut left = attacks;
+        while left > 0 {
+            let Some(front) = self.garbage.front_mut() else { break };
+            let cancel = front.lines.min(left);
+            front.lines -= cancel;
+            left -= cancel;
+            if front.lines == 0 {
+                self.garbage.pop_front();
+            }
+        }
 
-        Node {
-            eval,
-            mv: mov,
-            children: vec![],
-            expansions: 0,
-            expanding: false,
+        let mut received = 0;
+        if clears == 0 {
+            while let Some(garbage) = self.garbage.front() {
+                if garbage.delay > 0 {
+                    break;
+                }
+                self.board.add_garbage(garbage.lines, garbage.col);
+                received += garbage.lines;
+                self.garbage.pop_front();
+            }
+        }
+        for garbage in self.garbage.iter_mut() {
+            garbage.delay = garbage.delay.saturating_sub(1);
         }
+
+        (attacks - left, received)
     }
 
 
-    /// Applies move onto state, returning the resultant child AND THE LINES CLEARED.
-    /// Wraps `apply_move_return_clears(..)`
+    /// Applies the move, then evaluates the placement. Returns the child state and its evaluation.
+    pub fn make_child (self, mov: &Move, eval_mode: eval::Mode, config: &eval::EvalConfig) -> (Self, f32) {
+        let (state, stats) = self.apply_move_with_stats(mov);
+        let eval = eval::evaluate(&state, stats, eval_mode, config);
+        (state, eval)
+    }
+
+
+    /// Applies move onto state, returning the resultant child.
+    /// Wraps `apply_move_with_stats(..)`, since attack is needed to cancel garbage.
     pub fn apply_move (self, mov: &Move) -> Self {
-        self.apply_move_return_clears(mov).0
+        self.apply_move_with_stats(mov).0
     }
 
     pub fn queue_len (&self) -> usize {
         self.queue.len()
     }
-}
 
-/// Tetr.io garbage table 
-pub const B2B_TABLE: [[[u32; 10]; 4]; 4] = [
-    [
-        [4, 5, 6, 7, 8, 9, 10, 11, 12, 13],
-        [2, 2, 3, 3, 4, 4, 5, 5, 6, 6],
-        [4, 5, 6, 7, 8, 9, 10, 11, 12, 13],
-        [6, 7, 9, 10, 12, 13, 15, 16, 18, 19],
-    ],
-    [
-        [5, 6, 7, 8, 10, 11, 12, 13, 15, 16],
-        [3, 3, 4, 5, 6, 6, 7, 8, 9, 9],
-        [5, 6, 7, 8, 10, 11, 12, 13, 15, 16],
-        [7, 8, 10, 12, 14, 15, 17, 19, 21, 22],
-    ],
-    [
-        [6, 7, 9, 10, 12, 13, 15, 16, 18, 19],
-        [4, 5, 6, 7, 8, 9, 10, 11, 12, 13],
-        [6, 7, 9, 10, 12, 13, 15, 16, 18, 19],
-        [8, 10, 12, 14, 16, 18, 20, 22, 24, 25],
-    ],
-    [
-        [7, 8, 10, 12, 14, 15, 17, 19, 21, 22],
-        [5, 6, 7, 8, 10, 11, 12, 13, 15, 16],
-        [7, 8, 10, 12, 14, 15, 17, 19, 21, 22],
-        [9, 11, 13, 15, 18, 20, 22, 24, 27, 29],
-    ],
-];
+    /// Returns the piece a move would place from this state, taking hold into account.
+    pub fn placed_piece (&self, mov: &Move) -> Option<Piece> {
+        if mov.held() {
+            self.hold.or_else(|| self.queue.get(1).copied())
+        } else {
+            self.queue.front().copied()
+        }
+    }
+
+    pub fn push_piece (&mut self, piece: Piece) {
+        self.queue.push_back(piece);
+    }
+}
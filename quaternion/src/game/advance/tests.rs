@@ -58,3 +58,200 @@ fn clears () {
 }
 
 
+
+
+/// T placement whose last input was a rotation using the given kick.
+fn t_spun (x: i8, y: i8, r: Rotation, kick: u8) -> Move {
+    let mut mov = Move { x, y, r, kick, ..Move::default() };
+    mov.add_key(&Key::CW);
+    mov
+}
+
+fn t_state (board: &str) -> State {
+    State {
+        board: Board::from_str(board),
+        queue: vec![Piece::T].into_iter().collect(),
+        ..State::default()
+    }
+}
+
+#[test]
+fn tspin_full () {
+    let board = "
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    # . . . . . . . . .
+    . . . # # # # # # #
+    # . # # # # # # # #
+    ";
+
+    let (state, stats) = t_state(board).apply_move_with_stats(&t_spun(1, 1, Rotation::S, 0));
+    assert_eq!(stats.spin, Spin::Full);
+    assert_eq!(stats.ds, 2);
+    assert_eq!(stats.attacks, 4);
+    assert_eq!(state.b2b, 1);
+}
+
+#[test]
+fn tspin_mini () {
+    let board = "
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    # . . . . . . . . .
+    . . . # # # # # # #
+    ";
+
+    let (state, stats) = t_state(board).apply_move_with_stats(&t_spun(1, 0, Rotation::N, 0));
+    assert_eq!(stats.spin, Spin::Mini);
+    assert_eq!(stats.ds, 1);
+    assert_eq!(stats.attacks, 0);
+    assert_eq!(state.b2b, 1);
+
+    // TST kick upgrades the mini
+    let (_, stats) = t_state(board).apply_move_with_stats(&t_spun(1, 0, Rotation::N, 4));
+    assert_eq!(stats.spin, Spin::Full);
+    assert_eq!(stats.attacks, 2);
+
+    // Not a spin if the last input was not a rotation
+    let mut mov = Move { x: 1, y: 0, ..Move::default() };
+    mov.add_key(&Key::Drop);
+    let (_, stats) = t_state(board).apply_move_with_stats(&mov);
+    assert_eq!(stats.spin, Spin::None);
+}
+
+#[test]
+fn all_spin () {
+    let board = "
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    . . . . . . . . . .
+    # . . # # # # # # #
+    . . # # # # # # # #
+    # # # # # . # # # #
+    ";
+    let state = State {
+        board: Board::from_str(board),
+        queue: vec![Piece::S].into_iter().collect(),
+        ..State::default()
+    };
+
+    // S spun into an immobile spot
+    let mut mov = Move { x: 1, y: 1, ..Move::default() };
+    mov.add_key(&Key::CCW);
+
+    let rules = |spins| Rules { spins, ..Rules::default() };
+
+    let (_, stats) = state.clone().apply_move_with_stats(&mov);
+    assert_eq!(stats.spin, Spin::None);
+    assert_eq!(stats.attacks, 1);
+
+    let (_, stats) = state.clone().with_rules(rules(SpinRules::AllMini)).apply_move_with_stats(&mov);
+    assert_eq!(stats.spin, Spin::Mini);
+    assert_eq!(stats.piece, Piece::S);
+    assert_eq!(stats.attacks, 1);
+
+    let (next, stats) = state.clone().with_rules(rules(SpinRules::AllSpin)).apply_move_with_stats(&mov);
+    assert_eq!(stats.spin, Spin::Full);
+    assert_eq!(stats.attacks, 4);
+    assert_eq!(next.b2b, 1);
+
+    // Mobile pieces don't spin
+    let mut mov = Move { x: 4, y: 3, ..Move::default() };
+    mov.add_key(&Key::CCW);
+    let (_, stats) = state.with_rules(rules(SpinRules::AllSpin)).apply_move_with_stats(&mov);
+    assert_eq!(stats.spin, Spin::None);
+}
+
+#[test]
+fn garbage () {
+    let flat = Move { x: 4, y: 0, r: Rotation::N, ..Move::default() };
+
+    // Due garbage enters on a placement without clears
+    let mut state = t_state("");
+    state.push_garbage(Garbage { lines: 3, col: 2, delay: 0 });
+    state.push_garbage(Garbage { lines: 2, col: 7, delay: 2 });
+    let (state, stats) = state.apply_move_with_stats(&flat);
+    assert_eq!((stats.cancelled, stats.received), (0, 3));
+    assert_eq!(state.board.v[2] & 0b111, 0);
+    assert_eq!(state.board.v[4], 0b11111);
+    assert_eq!(state.incoming(), 2);
+    assert_eq!(state.garbage().next().unwrap().delay, 1);
+
+    // Attack cancels, and clears hold garbage back
+    let mut state = t_state("
+    # # # # . # # # # #
+    # # # . . . # # # #
+    # # # # # # # # # .
+    ");
+    let flat = Move { y: 1, ..flat };
+    state.push_garbage(Garbage { lines: 3, col: 0, delay: 0 });
+    let (state, stats) = state.apply_move_with_stats(&flat);
+    assert_eq!((stats.attacks, stats.cancelled, stats.received), (1, 1, 0));
+    assert_eq!(state.incoming(), 2);
+    assert_eq!(state.board, Board::from_str("# # # # # # # # # ."));
+}
+
+#[test]
+fn transposition_key () {
+    let left = Move { x: 0, y: 0, r: Rotation::N, ..Move::default() };
+    let right = Move { x: 5, ..left };
+    let state = State {
+        queue: vec![Piece::O, Piece::O, Piece::T].into_iter().collect(),
+        ..State::default()
+    };
+
+    // Same state through either move order
+    let a = state.clone().apply_move(&left).apply_move(&right);
+    let b = state.clone().apply_move(&right).apply_move(&left);
+    assert!(a == b);
+    assert_eq!(a.key(), b.key());
+
+    // Different placements do not collide
+    let c = state.apply_move(&left).apply_move(&Move { x: 2, ..left });
+    assert_ne!(a.key(), c.key());
+}
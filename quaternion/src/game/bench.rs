@@ -0,0 +1,23 @@
+use super::*;
+use super::eval::Tspins;
+use super::movegen::ConflictTable;
+
+
+/*
+ * The board-level hot paths, which need the board. Re-exported by `crate::bench`.
+ */
+
+/// Builds the conflict table of `piece` on the state's board.
+pub fn conflict_table (state: &State, piece: Piece) {
+    std::hint::black_box(ConflictTable::from(&state.board, piece));
+}
+
+/// Clears the full rows of a copy of the state's board. Returns how many.
+pub fn clear (state: &State) -> u32 {
+    state.board.clone().clear()
+}
+
+/// T-spin slots on the state's board.
+pub fn tspins (state: &State) -> u32 {
+    Tspins::find(&state.board).count()
+}
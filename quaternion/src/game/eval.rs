@@ -1,7 +1,11 @@
 #[cfg(test)]
 mod tests;
+#[cfg(feature = "serde")]
+mod patch;
 
 use crate::game::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 
 pub enum Mode {
@@ -11,28 +15,34 @@ pub enum Mode {
 }
 
 
-struct Factors {
-    ideal_h: f32,
-    well_threshold: f32,
-    hole_depth_relevancy_threshold: u32 // holes deeper than this are ignored, since they
-                                        // aren't important in the near future.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Factors {
+    pub ideal_h: f32,
+    pub well_threshold: f32,
+    pub hole_depth_relevancy_threshold: u32 // holes deeper than this are ignored, since they
+                                            // aren't important in the near future.
 }
 
-struct Weights {
-    hole: f32,
-    hole_depth: f32,
-    h_local_deviation: f32,
-    h_global_deviation: f32,
-    well_v: f32,
-    well_parity: f32,
-    well_odd_par: f32,
-    well_flat_parity: f32,
-    tspin_bonus: f32,
-    tspin_score: f32,
-    average_h: f32,
-    attack: f32,
-    downstack: f32,
-    eff: f32,
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Weights {
+    pub hole: f32,
+    pub hole_depth: f32,
+    pub h_local_deviation: f32,
+    pub h_global_deviation: f32,
+    pub well_v: f32,
+    pub well_parity: f32,
+    pub well_odd_par: f32,
+    pub well_flat_parity: f32,
+    pub tspin_bonus: f32,
+    pub tspin_score: f32,
+    pub tspin_mini: f32,
+    pub average_h: f32,
+    pub attack: f32,
+    pub downstack: f32,
+    pub eff: f32,
+    pub incoming: f32,
 } 
 
 const WEIGHTS_ATK: Weights = Weights {
@@ -46,10 +56,12 @@ const WEIGHTS_ATK: Weights = Weights {
     well_flat_parity: 0.0,
     tspin_bonus: 25.0,
     tspin_score: 25.0,
+    tspin_mini: -20.0,
     average_h : 0.0,
     attack: 100.0,
     downstack: 10.0,
     eff: 130.0,
+    incoming: -40.0,
 };
 
 const WEIGHTS_DS: Weights = Weights {
@@ -63,10 +75,12 @@ const WEIGHTS_DS: Weights = Weights {
     well_flat_parity: 0.0,
     tspin_bonus: 0.0,
     tspin_score: 0.0,
+    tspin_mini: 0.0,
     average_h : -20.0,
     attack: 0.0,
     downstack: 100.0,
     eff: 0.0,
+    incoming: -60.0,
 };
 
 const FACTORS_ATK: Factors = Factors {
@@ -86,6 +100,42 @@ const DS_MODE_PENALTY    : f32 = 0.0;
 const WELL_PLACEMENT_F   : f32 = 70.0;
 const WELL_PLACEMENT     : [f32; 10] = [-0.5, -1.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, -1.0, -0.5];
 
+/// Every tunable of the evaluation. Each bot carries its own; defaults to the consts above.
+/// Fields missing when deserializing are taken from the default, in nested tables too.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct EvalConfig {
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "patch::weights_atk"))]
+    pub weights_atk: Weights,
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "patch::weights_ds"))]
+    pub weights_ds: Weights,
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "patch::factors_atk"))]
+    pub factors_atk: Factors,
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "patch::factors_ds"))]
+    pub factors_ds: Factors,
+    /// Average height (pending garbage included) past which DS weights are used.
+    pub ds_height_threshold: f32,
+    pub ds_mode_penalty: f32,
+    pub well_placement_f: f32,
+    /// Preference of well columns.
+    pub well_placement: [f32; 10],
+}
+
+impl Default for EvalConfig {
+    fn default () -> Self {
+        Self {
+            weights_atk: WEIGHTS_ATK,
+            weights_ds: WEIGHTS_DS,
+            factors_atk: FACTORS_ATK,
+            factors_ds: FACTORS_DS,
+            ds_height_threshold: DS_HEIGHT_THRESHOLD,
+            ds_mode_penalty: DS_MODE_PENALTY,
+            well_placement_f: WELL_PLACEMENT_F,
+            well_placement: WELL_PLACEMENT,
+        }
+    }
+}
+
 
 struct Tspin {
     x: usize,
@@ -94,14 +144,14 @@ struct Tspin {
 }
 
 #[derive(Default)]
-struct Tspins {
+pub(super) struct Tspins {
     vx: Vec<usize>,
     pub overhangs: u8,
     pub score: u8
 }
 
 impl Tspins {
-    fn count (&self) -> u32 {
+    pub(super) fn count (&self) -> u32 {
         self.vx.len() as u32
     }
 
@@ -109,10 +159,10 @@ impl Tspins {
         self.vx.contains(&x)
     }
 
-    fn find (board: &Board) -> Self {
+    pub(super) fn find (board: &Board) -> Self {
         let mut out = Tspins::default();
 
-        for y in 0..20 {
+        for y in 0..VISIBLE_HEIGHT as i32 {
             for x in 0..10 {
                 if let Some(tspin) = Self::is_tspin(&board, x, y) {
                     out.vx.push(tspin.x);
@@ -198,7 +248,7 @@ impl Tspins {
 }
 
 /// Heuristic Evaluation function
-pub fn evaluate (state: &State, meta: MoveStats, mode: Mode) -> f32 {
+pub fn evaluate (state: &State, meta: MoveStats, mode: Mode, config: &EvalConfig) -> f32 {
 
 
     let mut score = 0.0;
@@ -208,22 +258,25 @@ pub fn evaluate (state: &State, meta: MoveStats, mode: Mode) -> f32 {
     let tspins = Tspins::find(&state.board);
 
     // Calc heights
-    let h = b.v.map(|col| 32-col.leading_zeros());
+    let h = b.v.map(|col| 64-col.leading_zeros());
     let avg_h = h.iter().sum::<u32>() as f32 / 10.0;
 
+    // Pending garbage, counted as if it had already arrived.
+    let incoming = state.incoming() as f32;
+
     // Select weights
     // Will use DS if average height past threshold
     let (weights, factors) = {
         match mode {
             Mode::Norm =>
-                if  avg_h >= DS_HEIGHT_THRESHOLD {
-                    score += DS_MODE_PENALTY;
-                    (WEIGHTS_DS, FACTORS_DS)
+                if  avg_h + incoming >= config.ds_height_threshold {
+                    score += config.ds_mode_penalty;
+                    (&config.weights_ds, &config.factors_ds)
                 } else {
-                    (WEIGHTS_ATK, FACTORS_ATK)
+                    (&config.weights_atk, &config.factors_atk)
                 },
-            Mode::DS => (WEIGHTS_DS, FACTORS_DS),
-            Mode::Attack => (WEIGHTS_ATK, FACTORS_ATK),
+            Mode::DS => (&config.weights_ds, &config.factors_ds),
+            Mode::Attack => (&config.weights_atk, &config.factors_atk),
         }
     };
 
@@ -350,7 +403,7 @@ pub fn evaluate (state: &State, meta: MoveStats, mode: Mode) -> f32 {
             .count_ones();
 
         score += well_value as f32 * weights.well_v;
-        score += WELL_PLACEMENT_F * WELL_PLACEMENT[well_x];
+        score += config.well_placement_f * config.well_placement[well_x];
 
         // Parity: penalize large parity diffs, bonus for flat well.
         let d = (if well_x != 0 {h[well_x-1]} else {h[well_x+1]}).abs_diff(if well_x != 9 {h[well_x+1]} else {h[well_x-1]});
@@ -376,9 +429,14 @@ pub fn evaluate (state: &State, meta: MoveStats, mode: Mode) -> f32 {
     score += (meta.attacks as i32 - meta.ds as i32) as f32 * weights.eff;
     score += meta.attacks as f32 * weights.attack;
     score += meta.ds as f32 * weights.downstack;
+
+    // Garbage left uncancelled
+    score += incoming * weights.incoming;
+
+    // T minis spend a T for little attack
+    if meta.spin == Spin::Mini && meta.piece == Piece::T {
+        score += weights.tspin_mini;
+    }
     
-    // if meta.tspin && meta.attacks == 4 {
-    //     println!("tspin found with attack: {}, score: {}", meta.attacks, score);
-    // }
     score
 }
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Deserializer};
+
+use super::*;
+
+
+/*
+ * Nested tables of an `EvalConfig` are read over their built-in values, so a file may give a few
+ * fields of `weights_atk` and keep the rest. `Weights` and `Factors` have no default of their own:
+ * attack and downstack differ.
+ */
+
+/// A struct with every field optional, applied over a base.
+macro_rules! patch {
+    ($patch:ident for $ty:ident { $($field:ident: $fty:ty),* $(,)? }) => {
+        #[derive(Deserialize)]
+        struct $patch {
+            $($field: Option<$fty>),*
+        }
+
+        impl $patch {
+            fn over (self, base: $ty) -> $ty {
+                $ty { $($field: self.$field.unwrap_or(base.$field)),* }
+            }
+        }
+    };
+}
+
+patch!(WeightsPatch for Weights {
+    hole: f32,
+    hole_depth: f32,
+    h_local_deviation: f32,
+    h_global_deviation: f32,
+    well_v: f32,
+    well_parity: f32,
+    well_odd_par: f32,
+    well_flat_parity: f32,
+    tspin_bonus: f32,
+    tspin_score: f32,
+    tspin_mini: f32,
+    average_h: f32,
+    attack: f32,
+    downstack: f32,
+    eff: f32,
+    incoming: f32,
+});
+
+patch!(FactorsPatch for Factors {
+    ideal_h: f32,
+    well_threshold: f32,
+    hole_depth_relevancy_threshold: u32,
+});
+
+pub(super) fn weights_atk<'de, D: Deserializer<'de>> (d: D) -> Result<Weights, D::Error> {
+    WeightsPatch::deserialize(d).map(|patch| patch.over(WEIGHTS_ATK))
+}
+
+pub(super) fn weights_ds<'de, D: Deserializer<'de>> (d: D) -> Result<Weights, D::Error> {
+    WeightsPatch::deserialize(d).map(|patch| patch.over(WEIGHTS_DS))
+}
+
+pub(super) fn factors_atk<'de, D: Deserializer<'de>> (d: D) -> Result<Factors, D::Error> {
+    FactorsPatch::deserialize(d).map(|patch| patch.over(FACTORS_ATK))
+}
+
+pub(super) fn factors_ds<'de, D: Deserializer<'de>> (d: D) -> Result<Factors, D::Error> {
+    FactorsPatch::deserialize(d).map(|patch| patch.over(FACTORS_DS))
+}
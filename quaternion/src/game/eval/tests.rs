@@ -24,7 +24,7 @@ fn eval1 () {
 # . . . . # # . . .
 # # . # # # # # # #
     ";
-    let a_stats = MoveStats { attacks: 0, ds: 0, tspin: false };
+    let a_stats = MoveStats { attacks: 0, ds: 0, spin: Spin::None, piece: Piece::None, cancelled: 0, received: 0 };
     let b = "
 . . . . . . . . . .  b2b:    0
 . . . . . . . . . .  combo:  0
@@ -47,9 +47,9 @@ fn eval1 () {
 . # # # # # # # # #
 # # . # # # # # # #
     ";
-    let b_stats = MoveStats { attacks: 0, ds: 0, tspin: false };
+    let b_stats = MoveStats { attacks: 0, ds: 0, spin: Spin::None, piece: Piece::None, cancelled: 0, received: 0 };
 
-    assert!(evaluate(&State::from_str(&a), a_stats, Mode::Norm) > evaluate(&State::from_str(&b), b_stats, Mode::Norm));
+    assert!(evaluate(&State::from_str(&a), a_stats, Mode::Norm, &EvalConfig::default()) > evaluate(&State::from_str(&b), b_stats, Mode::Norm, &EvalConfig::default()));
 }
 
 #[test]
@@ -76,7 +76,7 @@ fn eval2 () {
 # . # # # # # # # #
 # . # # # # # # # #
     ";
-    let a_stats = MoveStats { attacks: 4, ds: 2, tspin: true };
+    let a_stats = MoveStats { attacks: 4, ds: 2, spin: Spin::Full, piece: Piece::T, cancelled: 0, received: 0 };
     let b = "
 . . . . . . . . . .  b2b:    0
 . . . . . . . . . .  combo:  0
@@ -99,9 +99,9 @@ fn eval2 () {
 # . # # # # # # # #
 # . # # # # # # # #
     ";
-    let b_stats = MoveStats { attacks: 0, ds: 0, tspin: false };
+    let b_stats = MoveStats { attacks: 0, ds: 0, spin: Spin::None, piece: Piece::None, cancelled: 0, received: 0 };
 
-    assert!(evaluate(&State::from_str(&a), a_stats, Mode::Norm) > evaluate(&State::from_str(&b), b_stats, Mode::Norm));
+    assert!(evaluate(&State::from_str(&a), a_stats, Mode::Norm, &EvalConfig::default()) > evaluate(&State::from_str(&b), b_stats, Mode::Norm, &EvalConfig::default()));
 }
 
 #[test]
@@ -128,7 +128,7 @@ fn eval3 () {
 # # # . . . # # # #
 # # # # . # # # # #
 ";
-    let a_stats = MoveStats { attacks: 0, ds: 0, tspin: false };
+    let a_stats = MoveStats { attacks: 0, ds: 0, spin: Spin::None, piece: Piece::None, cancelled: 0, received: 0 };
     let b = "
 . . . . . . . . . .  b2b:    0
 . . . . . . . . . .  combo:  0
@@ -151,7 +151,7 @@ fn eval3 () {
 # # # . . # # # # #
 # # # # . # # # # #
     ";
-    let b_stats = MoveStats { attacks: 0, ds: 0, tspin: false };
+    let b_stats = MoveStats { attacks: 0, ds: 0, spin: Spin::None, piece: Piece::None, cancelled: 0, received: 0 };
 
-    assert!(evaluate(&State::from_str(&a), a_stats, Mode::Norm) > evaluate(&State::from_str(&b), b_stats, Mode::Norm));
+    assert!(evaluate(&State::from_str(&a), a_stats, Mode::Norm, &EvalConfig::default()) > evaluate(&State::from_str(&b), b_stats, Mode::Norm, &EvalConfig::default()));
 }
@@ -1,33 +1,64 @@
 #[cfg(test)]
 mod tests;
+#[cfg(test)]
+mod reference;
 
 use crate::game::*;
+use super::advance::TST_KICK;
 use std::collections::{HashSet, LinkedList};
 
 
 /// Returns a list of all possible moves from this current state. Includes hold piece.
-/// Wraps private 'gen_moves_one(..)'.
+/// Wraps private 'gen_moves_one(..)'. Returns no moves if the queue is empty.
 pub fn gen_moves (state: &State) -> Vec<Move> {
-    let piece = state.queue
-        .front()
-        .expect("State has no pieces in queue. cannot generate moves.")
-        .clone();
+    let Some(&piece) = state.queue.front() else { return vec![] };
 
-    gen_moves_one(&state.board, piece, false)
+    let flip = state.rules.flip;
+    gen_moves_one(&state.board, piece, false, flip)
         .into_iter()
         .chain(
             if let Some(hold) = state.hold {
-                gen_moves_one(&state.board, hold, true)
+                gen_moves_one(&state.board, hold, true, flip)
             } else if let Some(&hold) = state.queue.get(1) {
-                gen_moves_one(&state.board, hold, true)
+                gen_moves_one(&state.board, hold, true, flip)
             }  else { vec![] }.into_iter()
         )
         .collect()
 }
 
+impl State {
+    /// Exported wrapper of `gen_moves(..)`. Returns no moves if the queue is empty.
+    pub fn legal_moves (&self) -> Vec<Move> {
+        gen_moves(self)
+    }
+
+    /// Checks an untrusted move: replays its keys from spawn, with the piece it holds or not, and
+    /// the rules' rotations. Returns the move as replayed, with the kick of its last rotation, which
+    /// spin detection relies on.
+    pub fn validate_move (&self, mov: &Move) -> Result<Move, ReplayError> {
+        let keys = mov.keys()?;
+        if !self.rules.flip && keys.contains(&Key::Flip) {
+            return Err(ReplayError::Flip);
+        }
+        let piece = if keys.first() == Some(&Key::Hold) {
+            self.hold.or_else(|| self.queue.get(1).copied())
+        } else {
+            self.queue.front().copied()
+        };
+        let piece = piece.ok_or(ReplayError::NoPiece)?;
+
+        let replayed = mov.replay(&self.board, piece)?;
+        if (replayed.x, replayed.y, replayed.r) != (mov.x, mov.y, mov.r) {
+            return Err(ReplayError::Mismatch { x: replayed.x, y: replayed.y, r: replayed.r });
+        }
+        Ok(replayed)
+    }
+}
+
 /// Returns a list of all possible moves from a board for a single piece.
 /// Wrapped by 'gen_moves(..)' for exported interface.
-fn gen_moves_one (board: &Board, piece: Piece, hold: bool) -> Vec<Move> {
+/// 180 rotations are only tried if `flip` is set.
+fn gen_moves_one (board: &Board, piece: Piece, hold: bool, flip: bool) -> Vec<Move> {
     let mut queue: LinkedList<Move> = LinkedList::new();
     let mut set: HashSet<u32> = HashSet::new();
     let mut out: LinkedList<Move> = LinkedList::new();
@@ -36,10 +67,11 @@ fn gen_moves_one (board: &Board, piece: Piece, hold: bool) -> Vec<Move> {
     // Convenience function for updating BFS state & output.
     let mut update = |queue: &mut LinkedList<Move>, set: &mut HashSet<u32>, mov: Move| {
         // Insert to set
-        if set.insert(mov.canon()) {
+        if set.insert(mov.canon(piece)) {
             
             // If touching stack (y-1 conflicts), add to output.
-            if conflict_table.touches(&mov) {
+            // Placements entirely above the visible field lock out, and are not legal.
+            if conflict_table.touches(&mov) && !mov.locks_out(piece) {
                 out.push_back(mov.clone())
             }
 
@@ -50,20 +82,15 @@ fn gen_moves_one (board: &Board, piece: Piece, hold: bool) -> Vec<Move> {
         }
     };
 
-    // Add Spawn
+    // Add Spawn.
     {
-        let mut spawn = Move {
-            x: 4,
-            y: 19,
-            r: Rotation::N,
-            list: 0,
-        };
+        let mut spawn = Move::spawn();
 
         if hold {
             spawn.add_key(&Key::Hold);
         }
 
-        // If spawn conflicts, return no moves. game over.
+        // If spawn conflicts, return no moves. game over (block out).
         if conflict_table.conflicts(&spawn) {
             return vec![];
         }
@@ -91,6 +118,11 @@ fn gen_moves_one (board: &Board, piece: Piece, hold: bool) -> Vec<Move> {
         if let Some(mov) = mov.ccw(&conflict_table) {
             update(&mut queue, &mut set, mov);
         }
+        if flip {
+            if let Some(mov) = mov.flip(&conflict_table) {
+                update(&mut queue, &mut set, mov);
+            }
+        }
     }
 
 
@@ -106,7 +138,7 @@ impl Board {
         assert!(x >= 0 && x < 10 && y >= 0);
         if y == 0 { return 0 }
         let col = self.v[x as usize];
-        ((col << (32 - y)).leading_zeros() as i8).min(y)
+        ((col << (64 - y)).leading_zeros() as i8).min(y)
     }
 }
 
@@ -158,7 +190,7 @@ impl Move {
             Rotation::E => Rotation::S,
             Rotation::W => Rotation::N,
         };
-        self.rotate(conflict_table, self.r, nr)
+        self.rotate(conflict_table, nr, &Rotation::kicktable(conflict_table.piece, self.r, nr))
             .map(|mut mov| {
                 mov.add_key(&Key::CW);
                 mov
@@ -173,21 +205,30 @@ impl Move {
             Rotation::E => Rotation::N,
             Rotation::W => Rotation::S,
         };
-        self.rotate(conflict_table, self.r, nr)
+        self.rotate(conflict_table, nr, &Rotation::kicktable(conflict_table.piece, self.r, nr))
             .map(|mut mov| {
                 mov.add_key(&Key::CCW);
                 mov
             })
     }
 
-    fn rotate(&self, conflict_table: &ConflictTable, from: Rotation, to: Rotation) -> Option<Move> {
-        let kicks = Rotation::kicktable(conflict_table.piece, from, to);
+    /// Applies 180 rotation to the move, outputs if it works and is different.
+    fn flip (&self, conflict_table: &ConflictTable) -> Option<Move> {
+        let nr = self.r.opposite();
+        self.rotate(conflict_table, nr, &Rotation::flip_kicktable(conflict_table.piece, self.r))
+            .map(|mut mov| {
+                mov.add_key(&Key::Flip);
+                mov
+            })
+    }
 
-        for kick in kicks {
+    fn rotate(&self, conflict_table: &ConflictTable, to: Rotation, kicks: &[(i8, i8)]) -> Option<Move> {
+        for (i, kick) in kicks.iter().enumerate() {
             let nmov = Move {
                 x: self.x + kick.0,
                 y: self.y + kick.1,
                 r: to,
+                kick: i as u8,
                 ..*self
             };
             if !conflict_table.conflicts(&nmov) {
@@ -199,22 +240,24 @@ impl Move {
 }
 
 
-struct ConflictTable {
+pub(super) struct ConflictTable {
     // 0 represents no conflict.
-    v: [[u32; 10]; 4],
+    v: [[u64; 10]; 4],
     pub piece: Piece,
 }
 
 impl ConflictTable {
 
     /// Creates conflict table given board & piece.
-    fn from (board: &Board, piece: Piece) -> Self {
+    /// Rows at and above `BOARD_HEIGHT` are treated as a ceiling.
+    pub(super) fn from (board: &Board, piece: Piece) -> Self {
         let mut v = [[0; 10]; 4];
+        let ceiling: u64 = !0 << BOARD_HEIGHT;
 
         for r in [Rotation::N, Rotation::S, Rotation::E, Rotation::W] {
             for (dx, dy) in piece.cells(r) {
                 for x in 0..10 {
-                    let mask = board.v.get((x + dx) as usize).copied().unwrap_or(!0);
+                    let mask = board.v.get((x + dx) as usize).map(|col| col | ceiling).unwrap_or(!0);
                     
                     let mask = if dy < 0 { 
                         // Need to negate twice since we want the bits spawned by the shifting to
@@ -242,7 +285,7 @@ impl ConflictTable {
 
     /// Determines if move is conflicting with the stack.
     /// Fetches from precomputed table
-    fn conflicts (&self, mov: &Move) -> bool {
+    pub(super) fn conflicts (&self, mov: &Move) -> bool {
         if mov.x >= 0 && mov.x < 10 && mov.y >= 0 {
             self.v[mov.r as usize][mov.x as usize] & (1 << mov.y) != 0
         } else {
@@ -254,13 +297,83 @@ impl ConflictTable {
 
 
 impl Move {
+    /// Where pieces enter. Guideline spawns pieces right above the visible field.
+    const fn spawn () -> Self {
+        Move { x: 4, y: VISIBLE_HEIGHT as i8, r: Rotation::N, list: 0, kick: 0 }
+    }
+
+    /// Executes the key list from spawn, with SRS kicks, then lets the piece fall. Hold may only
+    /// come first, and is left to the caller: `piece` is the piece placed.
+    /// Returns where the piece lands, with the kick of its last rotation and this key list.
+    pub(super) fn replay (&self, board: &Board, piece: Piece) -> Result<Move, ReplayError> {
+        let keys = self.keys()?;
+        let conflict_table = ConflictTable::from(board, piece);
+        let mut mov = Move::spawn();
+        if conflict_table.conflicts(&mov) {
+            return Err(ReplayError::BlockOut);
+        }
+
+        for (i, key) in keys.iter().enumerate() {
+            let next = match key {
+                Key::Hold if i == 0 => None,
+                Key::Hold => return Err(ReplayError::MisplacedHold),
+                Key::L    => mov.shift(-1, &conflict_table),
+                Key::R    => mov.shift( 1, &conflict_table),
+                Key::CW   => mov.cw(&conflict_table),
+                Key::CCW  => mov.ccw(&conflict_table),
+                Key::Flip => mov.flip(&conflict_table),
+                Key::Drop => mov.drop(board, piece),
+            };
+            // Keys that do not move the piece, as into a wall, are no-ops. Steps add to the key
+            // list, which is not needed here.
+            if let Some(next) = next {
+                mov = Move { list: 0, ..next };
+            }
+        }
+
+        // Gravity
+        if let Some(landed) = mov.drop(board, piece) {
+            mov = landed;
+        }
+        if mov.locks_out(piece) {
+            return Err(ReplayError::LockOut);
+        }
+        Ok(Move { list: self.list, ..mov })
+    }
+
+    /// `parse_list(..)`, for replays.
+    fn keys (&self) -> Result<Vec<Key>, ReplayError> {
+        self.parse_list().map_err(|e| match e {
+            QuaternionError::InvalidKey(key) => ReplayError::InvalidKey(key),
+            e => unreachable!("key lists only fail on keys: {e}"),
+        })
+    }
+
+    /// Determines if the placement locks out, i.e. every cell is above the visible field.
+    fn locks_out (&self, piece: Piece) -> bool {
+        piece.cells(self.r)
+            .iter()
+            .all(|(_, dy)| self.y + dy >= VISIBLE_HEIGHT as i8)
+    }
+
     /// Creates the canonical representation of the move. Used for hashmapping.
-    fn canon (&self) -> u32 {
-        ((self.y as u32) << 16) + ((self.x as u32) << 8) + (self.r as u32)
+    /// Moves to the same spot are told apart by what spin detection reads of their last input, so
+    /// one of each spin outcome is kept.
+    fn canon (&self, piece: Piece) -> u32 {
+        // Code of the last key, as written by `add_key(..)`. Read in place, as this is hot.
+        let last = self.list_len()
+            .checked_sub(1)
+            .map(|i| (self.list >> (i * Self::W + Self::LEN_W)) & Self::MASK);
+        let spin = match last {
+            Some(3 | 4) if piece == Piece::T && self.kick == TST_KICK => 2,
+            Some(3 | 4 | 7) => 1,
+            _ => 0,
+        };
+        ((self.y as u32) << 16) + ((self.x as u32) << 8) + (spin << 2) + (self.r as u32)
     }
 
     /// Adds key to bitset list.
-    fn add_key (&mut self, key: &Key) {
+    pub(super) fn add_key (&mut self, key: &Key) {
         if !self.list_has_space() {
             panic!("tried adding to key list when list full.");
         }
@@ -272,6 +385,7 @@ impl Move {
             Key::CCW  => 4,
             Key::Drop => 5,
             Key::Hold => 6,
+            Key::Flip => 7,
         };
         let index = self.list_len();
         
@@ -360,6 +474,53 @@ impl Rotation {
         const TABLE: [[[[(i8, i8); 5]; 4]; 4]; 7] = Rotation::make_kicks();
         TABLE[piece as usize][from as usize][to as usize]
     }
+
+    const fn opposite(self) -> Self {
+        match self {
+            Rotation::N => Rotation::S,
+            Rotation::S => Rotation::N,
+            Rotation::E => Rotation::W,
+            Rotation::W => Rotation::E,
+        }
+    }
+
+    /// TETR.IO (SRS+) 180 kicks, given in true rotation.
+    const fn flip_offsets(from: Rotation) -> [(i8, i8); 6] {
+        match from {
+            Rotation::N => [(0, 0), ( 0,  1), ( 1,  1), (-1,  1), ( 1, 0), (-1, 0)],
+            Rotation::S => [(0, 0), ( 0, -1), (-1, -1), ( 1, -1), (-1, 0), ( 1, 0)],
+            Rotation::E => [(0, 0), ( 1,  0), ( 1,  2), ( 1,  1), ( 0, 2), ( 0, 1)],
+            Rotation::W => [(0, 0), (-1,  0), (-1,  2), (-1,  1), ( 0, 2), ( 0, 1)],
+        }
+    }
+
+    /// Pieces rotate about a cell rather than their true center. The first SRS offset corrects for it.
+    /// The O piece does not kick.
+    const fn make_flip_kicks() -> [[[(i8, i8); 6]; 4]; 7] {
+        for_each_piece!(piece in {
+            for_each_rotation!(from in {
+                let to = from.opposite();
+                let mut kicks = match piece {
+                    Piece::O => [(0, 0); 6],
+                    _ => Rotation::flip_offsets(from),
+                };
+                let dx = Rotation::offsets(piece, from)[0].0 - Rotation::offsets(piece, to)[0].0;
+                let dy = Rotation::offsets(piece, from)[0].1 - Rotation::offsets(piece, to)[0].1;
+                let mut i = 0;
+                while i < kicks.len() {
+                    kicks[i].0 += dx;
+                    kicks[i].1 += dy;
+                    i += 1;
+                }
+                kicks
+            })
+        })
+    }
+
+    fn flip_kicktable(piece: Piece, from: Self) -> [(i8, i8); 6] {
+        const TABLE: [[[(i8, i8); 6]; 4]; 7] = Rotation::make_flip_kicks();
+        TABLE[piece as usize][from as usize]
+    }
 }
 
 
@@ -0,0 +1,194 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::game::*;
+
+
+/*
+ * Reference move generator, for tests. Slow and literal: pieces are drawn in their guideline
+ * bounding boxes, rotate within them, and kick by the SRS tables (and TETR.IO's for 180s), as
+ * written in the guideline. Every step moves the piece cell by cell and checks each against the
+ * board. Shares nothing with `gen_moves` but the board and the key list's capacity.
+ */
+
+/// A piece in play: the bottom-left corner of its bounding box, and its rotation.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Falling {
+    piece: Piece,
+    x: i8,
+    y: i8,
+    pub r: Rotation,
+}
+
+/// A final placement: rotation, and the cells covered, sorted.
+pub type Placement = (Rotation, [(i8, i8); 4]);
+
+impl Falling {
+    /// Boxes spawn at columns 3 to 6 (4 and 5 for O), with the piece right above the visible field.
+    pub fn spawn (piece: Piece) -> Self {
+        let h = VISIBLE_HEIGHT as i8;
+        let (x, y) = match piece {
+            Piece::O => (4, h),
+            Piece::I => (3, h - 2),
+            _ => (3, h - 1),
+        };
+        Self { piece, x, y, r: Rotation::N }
+    }
+
+    /// Side of the bounding box, and the cells in it when spawned. `y` points up.
+    fn shape (piece: Piece) -> (i8, [(i8, i8); 4]) {
+        match piece {
+            Piece::I => (4, [(0, 2), (1, 2), (2, 2), (3, 2)]),
+            Piece::O => (2, [(0, 0), (1, 0), (0, 1), (1, 1)]),
+            Piece::J => (3, [(0, 2), (0, 1), (1, 1), (2, 1)]),
+            Piece::L => (3, [(2, 2), (0, 1), (1, 1), (2, 1)]),
+            Piece::S => (3, [(1, 2), (2, 2), (0, 1), (1, 1)]),
+            Piece::Z => (3, [(0, 2), (1, 2), (1, 1), (2, 1)]),
+            Piece::T => (3, [(1, 2), (0, 1), (1, 1), (2, 1)]),
+            Piece::None => panic!("no piece"),
+        }
+    }
+
+    pub fn cells (&self) -> [(i8, i8); 4] {
+        let (n, mut cells) = Self::shape(self.piece);
+        let turns = match self.r {
+            Rotation::N => 0,
+            Rotation::E => 1,
+            Rotation::S => 2,
+            Rotation::W => 3,
+        };
+        for _ in 0..turns {
+            // Clockwise, within the box.
+            for cell in cells.iter_mut() {
+                *cell = (cell.1, n - 1 - cell.0);
+            }
+        }
+        for cell in cells.iter_mut() {
+            *cell = (cell.0 + self.x, cell.1 + self.y);
+        }
+        cells.sort();
+        cells
+    }
+
+    pub fn placement (&self) -> Placement {
+        (self.r, self.cells())
+    }
+
+    /// Walls, floor and the rows above the board are solid.
+    fn occupied (board: &Board, x: i8, y: i8) -> bool {
+        if !(0..10).contains(&x) || y < 0 || y as usize >= BOARD_HEIGHT {
+            return true
+        }
+        board.v[x as usize] & (1 << y) != 0
+    }
+
+    fn fits (&self, board: &Board) -> bool {
+        self.cells().iter().all(|&(x, y)| !Self::occupied(board, x, y))
+    }
+
+    fn moved (&self, dx: i8, dy: i8) -> Self {
+        Self { x: self.x + dx, y: self.y + dy, ..*self }
+    }
+
+    /// Whether the piece would lock: it cannot move down.
+    fn resting (&self, board: &Board) -> bool {
+        !self.moved(0, -1).fits(board)
+    }
+
+    /// Every cell above the visible field.
+    fn locks_out (&self) -> bool {
+        self.cells().iter().all(|&(_, y)| y as usize >= VISIBLE_HEIGHT)
+    }
+
+    /// The piece after a key. None if the key does not move it. Hold is not a movement.
+    pub fn apply (&self, board: &Board, key: Key) -> Option<Self> {
+        let out = match key {
+            Key::L => Some(self.moved(-1, 0)).filter(|f| f.fits(board)),
+            Key::R => Some(self.moved( 1, 0)).filter(|f| f.fits(board)),
+            Key::Drop => {
+                let mut out = *self;
+                while !out.resting(board) {
+                    out = out.moved(0, -1);
+                }
+                Some(out)
+            },
+            Key::CW | Key::CCW | Key::Flip => {
+                let to = match (key, self.r) {
+                    (Key::CW, Rotation::N) | (Key::CCW, Rotation::S) | (Key::Flip, Rotation::W) => Rotation::E,
+                    (Key::CW, Rotation::E) | (Key::CCW, Rotation::W) | (Key::Flip, Rotation::N) => Rotation::S,
+                    (Key::CW, Rotation::S) | (Key::CCW, Rotation::N) | (Key::Flip, Rotation::E) => Rotation::W,
+                    _ => Rotation::N,
+                };
+                let rotated = Self { r: to, ..*self };
+                kicks(self.piece, self.r, to)
+                    .into_iter()
+                    .map(|(dx, dy)| rotated.moved(dx, dy))
+                    .find(|f| f.fits(board))
+            },
+            Key::Hold => None,
+        };
+        out.filter(|f| f != self)
+    }
+}
+
+/// Kicks tried, in order, rotating from `from` to `to`. `y` points up.
+fn kicks (piece: Piece, from: Rotation, to: Rotation) -> Vec<(i8, i8)> {
+    use Rotation::*;
+
+    if piece == Piece::O {
+        return vec![(0, 0)];
+    }
+    match (from, to) {
+        // TETR.IO's 180 kicks.
+        (N, S) => vec![(0, 0), ( 0,  1), ( 1,  1), (-1,  1), ( 1, 0), (-1, 0)],
+        (S, N) => vec![(0, 0), ( 0, -1), (-1, -1), ( 1, -1), (-1, 0), ( 1, 0)],
+        (E, W) => vec![(0, 0), ( 1,  0), ( 1,  2), ( 1,  1), ( 0, 2), ( 0, 1)],
+        (W, E) => vec![(0, 0), (-1,  0), (-1,  2), (-1,  1), ( 0, 2), ( 0, 1)],
+        _ if piece == Piece::I => match (from, to) {
+            (N, E) | (W, S) => vec![(0, 0), (-2, 0), ( 1, 0), (-2, -1), ( 1,  2)],
+            (E, N) | (S, W) => vec![(0, 0), ( 2, 0), (-1, 0), ( 2,  1), (-1, -2)],
+            (E, S) | (N, W) => vec![(0, 0), (-1, 0), ( 2, 0), (-1,  2), ( 2, -1)],
+            (S, E) | (W, N) => vec![(0, 0), ( 1, 0), (-2, 0), ( 1, -2), (-2,  1)],
+            _ => unreachable!(),
+        },
+        (N, E) | (S, E) => vec![(0, 0), (-1, 0), (-1,  1), (0, -2), (-1, -2)],
+        (E, N) | (E, S) => vec![(0, 0), ( 1, 0), ( 1, -1), (0,  2), ( 1,  2)],
+        (S, W) | (N, W) => vec![(0, 0), ( 1, 0), ( 1,  1), (0, -2), ( 1, -2)],
+        (W, S) | (W, N) => vec![(0, 0), (-1, 0), (-1, -1), (0,  2), (-1,  2)],
+        _ => unreachable!(),
+    }
+}
+
+/// Placements of `piece` reachable from spawn in as many keys as a move holds, holding first if
+/// `hold`. None if the piece cannot spawn.
+pub fn placements (board: &Board, piece: Piece, hold: bool, flip: bool) -> HashSet<Placement> {
+    let spawn = Falling::spawn(piece);
+    if !spawn.fits(board) {
+        return HashSet::new();
+    }
+
+    let mut keys = vec![Key::L, Key::R, Key::CW, Key::CCW, Key::Drop];
+    if flip {
+        keys.push(Key::Flip);
+    }
+
+    // Breadth first, so each piece is first reached in the fewest keys.
+    let mut seen = HashSet::from([spawn]);
+    let mut queue = VecDeque::from([(spawn, hold as u64)]);
+    let mut out = HashSet::new();
+    while let Some((falling, len)) = queue.pop_front() {
+        if falling.resting(board) && !falling.locks_out() {
+            out.insert(falling.placement());
+        }
+        if len == Move::LIST_CAPACITY {
+            continue
+        }
+        for &key in &keys {
+            if let Some(next) = falling.apply(board, key) {
+                if seen.insert(next) {
+                    queue.push_back((next, len + 1));
+                }
+            }
+        }
+    }
+    out
+}
@@ -43,10 +43,11 @@ fn shift_l () {
         y: 19,
         r: Rotation::N,
         list: 0,
+        kick: 0,
     };
     let mov = mov.shift(1, &conflict_table).unwrap();
     assert_eq!(mov.x, 5);
-    assert_eq!(mov.parse_list(), vec![Key::R]);
+    assert_eq!(mov.parse_list().unwrap(), vec![Key::R]);
 
     // LEFT TUCK
     let piece = Piece::J;
@@ -70,10 +71,11 @@ fn shift_l () {
         y: 0,
         r: Rotation::N,
         list: 0,
+        kick: 0,
     }; 
     let mov = mov.shift(-1, &conflict_table).unwrap();
     assert_eq!(mov.x, 2);
-    assert_eq!(mov.parse_list(), vec![Key::L]);
+    assert_eq!(mov.parse_list().unwrap(), vec![Key::L]);
 }
 
 
@@ -89,13 +91,14 @@ fn rotate () {
         y: 19,
         r: Rotation::N,
         list: 0,
+        kick: 0,
     };
     let mov = mov.drop(board, piece).unwrap();
     let mov = mov.ccw(&conflict_table).unwrap();
     assert_eq!(mov.r, Rotation::W);
     assert_eq!(mov.x, 9);
     assert_eq!(mov.y, 1);
-    assert_eq!(mov.parse_list(), vec![Key::Drop, Key::CCW]);
+    assert_eq!(mov.parse_list().unwrap(), vec![Key::Drop, Key::CCW]);
 
     // TST 
     let piece = Piece::T;
@@ -119,6 +122,7 @@ fn rotate () {
         y: 19,
         r: Rotation::N,
         list: 0,
+        kick: 0,
     }; 
     let mov = mov.drop(&board, piece).unwrap();
     let mov = mov.shift(-1, &conflict_table).unwrap();
@@ -126,7 +130,7 @@ fn rotate () {
     assert_eq!(mov.x, 1);
     assert_eq!(mov.y, 1);
     assert_eq!(mov.r, Rotation::E);
-    assert_eq!(mov.parse_list(), vec![Key::Drop, Key::L, Key::CW]);
+    assert_eq!(mov.parse_list().unwrap(), vec![Key::Drop, Key::L, Key::CW]);
 }
 
 #[test]
@@ -154,10 +158,11 @@ fn drop () {
         y: 19,
         r: Rotation::N,
         list: 0,
+        kick: 0,
     };
     let mov = mov.drop(&board, piece).unwrap();
     assert_eq!(mov.y, 4);
-    assert_eq!(mov.parse_list(), vec![Key::Drop]);
+    assert_eq!(mov.parse_list().unwrap(), vec![Key::Drop]);
 
     // CLIP 
     let piece = Piece::T;
@@ -182,10 +187,11 @@ fn drop () {
         y: 19,
         r: Rotation::N,
         list: 0,
+        kick: 0,
     };
     let mov = mov.drop(&board, piece).unwrap();
     assert_eq!(mov.y, 3);
-    assert_eq!(mov.parse_list(), vec![Key::Drop]);
+    assert_eq!(mov.parse_list().unwrap(), vec![Key::Drop]);
 }
 
 
@@ -220,6 +226,8 @@ fn movegen () {
         b2b: 0,
         combo: 0,
         hold: None,
+        rules: Rules::default(),
+        garbage: VecDeque::new(),
     };
 
     let moves = gen_moves(&state);
@@ -231,3 +239,257 @@ fn movegen () {
 }
 
 
+
+#[test]
+fn spin_outcomes () {
+    // A T slot at column 1, reached by dropping the T in (no spin), or by kicking it in (a mini).
+    let mut v = [0; 10];
+    v[0] = 0b111;
+    v[2] = 0b1;
+    let state = State {
+        board: Board { v },
+        queue: vec![Piece::T].into_iter().collect(),
+        ..State::default()
+    };
+
+    let spins: Vec<Spin> = gen_moves(&state)
+        .iter()
+        .filter(|mov| (mov.x, mov.y, mov.r) == (1, 1, Rotation::E))
+        .map(|mov| state.clone().apply_move_with_stats(mov).1.spin)
+        .collect();
+    assert_eq!(spins.len(), 2);
+    assert!(spins.contains(&Spin::None));
+    assert!(spins.contains(&Spin::Mini));
+}
+
+
+#[test]
+fn flip () {
+    let piece = Piece::T;
+    let board = &TEST_BOARDS[0];
+    let conflict_table = ConflictTable::from(board, piece);
+
+    // Free 180
+    let mov = Move {
+        x: 4,
+        y: 10,
+        r: Rotation::N,
+        list: 0,
+        kick: 0,
+    };
+    let mov = mov.flip(&conflict_table).unwrap();
+    assert_eq!((mov.x, mov.y, mov.r), (4, 10, Rotation::S));
+    assert_eq!(mov.parse_list().unwrap(), vec![Key::Flip]);
+
+    // Kicked up off the floor
+    let mov = Move {
+        x: 4,
+        y: 0,
+        r: Rotation::N,
+        list: 0,
+        kick: 0,
+    };
+    let mov = mov.flip(&conflict_table).unwrap();
+    assert_eq!((mov.x, mov.y, mov.r), (4, 1, Rotation::S));
+
+    // I piece turns about its true center, landing a row lower
+    let conflict_table = ConflictTable::from(board, Piece::I);
+    let mov = Move {
+        x: 4,
+        y: 10,
+        r: Rotation::N,
+        list: 0,
+        kick: 0,
+    };
+    let mov = mov.flip(&conflict_table).unwrap();
+    assert_eq!((mov.x, mov.y, mov.r), (5, 9, Rotation::S));
+}
+
+#[test]
+fn flip_gated () {
+    let state = State {
+        board: TEST_BOARDS[0].clone(),
+        queue: vec![Piece::T].into_iter().collect(),
+        ..State::default()
+    };
+    let uses_flip = |moves: Vec<Move>| moves.iter().any(|mov| mov.parse_list().unwrap().contains(&Key::Flip));
+
+    assert!(!uses_flip(gen_moves(&state)));
+
+    let state = state.with_rules(Rules { flip: true, ..Rules::default() });
+    assert!(uses_flip(gen_moves(&state)));
+}
+
+#[test]
+fn top_out () {
+    let visible = (1 << VISIBLE_HEIGHT) - 1;
+
+    // Block out: spawn overlaps the stack
+    let mut board = TEST_BOARDS[0].clone();
+    board.v[4] = (1 << (VISIBLE_HEIGHT + 1)) - 1;
+    assert!(gen_moves_one(&board, Piece::T, false, false).is_empty());
+
+    // Lock out: only placements reaching into the visible field are legal
+    let mut board = TEST_BOARDS[0].clone();
+    for col in board.v.iter_mut().skip(1) {
+        *col = visible;
+    }
+    let moves = gen_moves_one(&board, Piece::T, false, false);
+    assert!(!moves.is_empty());
+    for mov in moves {
+        assert_eq!(mov.x, 0);
+        assert!(!mov.locks_out(Piece::T));
+    }
+    board.v[0] = visible;
+    assert!(gen_moves_one(&board, Piece::T, false, false).is_empty());
+}
+
+
+#[test]
+fn validate_move () {
+    let state = State {
+        board: TEST_BOARDS[0].clone(),
+        queue: vec![Piece::T, Piece::I].into_iter().collect(),
+        ..State::default()
+    };
+    let with_keys = |x: i8, y: i8, r: Rotation, keys: &[Key]| {
+        let mut mov = Move { x, y, r, list: 0, kick: 0 };
+        for key in keys {
+            mov.add_key(key);
+        }
+        mov
+    };
+
+    // Lands by gravity, without a drop key
+    assert!(state.validate_move(&with_keys(2, 0, Rotation::N, &[Key::L, Key::L])).is_ok());
+    // Keys into a wall do nothing
+    let wall = [Key::L; 6];
+    assert!(state.validate_move(&with_keys(1, 0, Rotation::N, &wall)).is_ok());
+    // Hold places the next piece
+    assert!(state.validate_move(&with_keys(4, 0, Rotation::N, &[Key::Hold])).is_ok());
+
+    assert_eq!(
+        state.validate_move(&with_keys(3, 0, Rotation::N, &[Key::L, Key::L])).err(),
+        Some(ReplayError::Mismatch { x: 2, y: 0, r: Rotation::N }),
+    );
+    assert_eq!(state.validate_move(&with_keys(4, 0, Rotation::N, &[Key::L, Key::Hold])).err(), Some(ReplayError::MisplacedHold));
+    assert_eq!(state.validate_move(&with_keys(4, 1, Rotation::S, &[Key::Flip])).err(), Some(ReplayError::Flip));
+    assert!(state.clone().with_rules(Rules { flip: true, ..Rules::default() })
+        .validate_move(&with_keys(4, 1, Rotation::S, &[Key::Flip]))
+        .is_ok());
+
+    // A list of one key, encoded as 0
+    let invalid = Move { list: 1, ..Move::default() };
+    assert_eq!(state.validate_move(&invalid).err(), Some(ReplayError::InvalidKey(0)));
+
+    let empty = State { queue: Default::default(), ..state.clone() };
+    assert_eq!(empty.validate_move(&with_keys(4, 0, Rotation::N, &[])).err(), Some(ReplayError::NoPiece));
+
+    let mut blocked = state.clone();
+    blocked.board.v[4] = (1 << (VISIBLE_HEIGHT + 1)) - 1;
+    assert_eq!(blocked.validate_move(&with_keys(4, 0, Rotation::N, &[])).err(), Some(ReplayError::BlockOut));
+
+    let mut full = state.clone();
+    full.board.v = [(1 << VISIBLE_HEIGHT) - 1; 10];
+    assert_eq!(full.validate_move(&with_keys(4, 20, Rotation::N, &[])).err(), Some(ReplayError::LockOut));
+}
+
+
+/*
+ * Against the reference generator, on random boards.
+ */
+
+use proptest::prelude::*;
+use super::reference::{self, Falling, Placement};
+
+fn piece () -> impl Strategy<Value = Piece> {
+    prop::sample::select(vec![Piece::J, Piece::L, Piece::S, Piece::Z, Piece::T, Piece::I, Piece::O])
+}
+
+/// Columns of random heights, with about a quarter of their cells knocked out. Some are tall enough
+/// to block spawns.
+fn board () -> impl Strategy<Value = Board> {
+    (prop::array::uniform10(0..=21u32), prop::array::uniform10(any::<u64>()), prop::array::uniform10(any::<u64>()))
+        .prop_map(|(heights, a, b)| {
+            let mut v = [0; 10];
+            for x in 0..10 {
+                v[x] = ((1 << heights[x]) - 1) & !(a[x] & b[x]);
+            }
+            Board { v }
+        })
+}
+
+fn placement (mov: &Move, piece: Piece) -> Placement {
+    let mut cells = piece.cells(mov.r).map(|(dx, dy)| (mov.x + dx, mov.y + dy));
+    cells.sort();
+    (mov.r, cells)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn matches_reference (board in board(), current in piece(), next in piece(), hold in prop::option::of(piece()), flip in any::<bool>()) {
+        let state = State {
+            board: board.clone(),
+            queue: vec![current, next].into_iter().collect(),
+            hold,
+            ..State::default()
+        }.with_rules(Rules { flip, ..Rules::default() });
+        let held = hold.unwrap_or(next);
+
+        let moves = gen_moves(&state);
+        let held_by = |mov: &Move| mov.parse_list().unwrap().first() == Some(&Key::Hold);
+        let found: HashSet<(bool, Placement)> = moves.iter()
+            .map(|mov| {
+                let hold = held_by(mov);
+                (hold, placement(mov, if hold { held } else { current }))
+            })
+            .collect();
+        // A placement may be reached once per spin outcome.
+        let outcomes: HashSet<(bool, u32)> = moves.iter()
+            .map(|mov| (held_by(mov), mov.canon(if held_by(mov) { held } else { current })))
+            .collect();
+        prop_assert_eq!(outcomes.len(), moves.len(), "duplicate moves");
+
+        let expected: HashSet<(bool, Placement)> = reference::placements(&board, current, false, flip)
+            .into_iter()
+            .map(|p| (false, p))
+            .chain(reference::placements(&board, held, true, flip).into_iter().map(|p| (true, p)))
+            .collect();
+        prop_assert_eq!(
+            found.difference(&expected).collect::<Vec<_>>(),
+            expected.difference(&found).collect::<Vec<_>>(),
+            "\n{}", board
+        );
+    }
+
+    #[test]
+    fn keys_replay (board in board(), current in piece(), hold in piece(), flip in any::<bool>()) {
+        let state = State {
+            board: board.clone(),
+            queue: vec![current].into_iter().collect(),
+            hold: Some(hold),
+            ..State::default()
+        }.with_rules(Rules { flip, ..Rules::default() });
+
+        for mov in gen_moves(&state) {
+            let keys = mov.parse_list().unwrap();
+            let (piece, keys) = match keys.split_first() {
+                Some((Key::Hold, rest)) => (hold, rest),
+                _ => (current, &keys[..]),
+            };
+
+            let mut falling = Falling::spawn(piece);
+            for &key in keys {
+                falling = falling.apply(&board, key)
+                    .unwrap_or_else(|| panic!("{key:?} of {keys:?} does not move the {piece:?}\n{board}"));
+            }
+            prop_assert_eq!(falling.placement(), placement(&mov, piece), "{:?}\n{}", keys, board);
+
+            let validated = state.validate_move(&mov);
+            prop_assert!(validated.as_ref().is_ok_and(|v| (v.x, v.y, v.r, v.kick, v.list) == (mov.x, mov.y, mov.r, mov.kick, mov.list)),
+                "{:?} validates as {:?}\n{}", mov, validated, board);
+        }
+    }
+}
@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod tests;
+
+use super::*;
+use serde::de::Error;
+use serde::{Deserializer, Serializer};
+
+/*
+ * Stable JSON representations of `Move` and `State`. Their in-memory layouts are bitsets;
+ * these spell them out, and are what fixtures and logs are written in.
+ */
+
+/// `Move` as written out. `hold` mirrors a leading `Hold` in `keys`.
+#[derive(Serialize, Deserialize)]
+struct MoveRepr {
+    x: i8,
+    y: i8,
+    rotation: Rotation,
+    hold: bool,
+    keys: Vec<Key>,
+    /// Kick used by the last rotation. Needed for spin detection; 0 if omitted.
+    #[serde(default)]
+    kick: u8,
+}
+
+impl Serialize for Move {
+    fn serialize<S: Serializer> (&self, serializer: S) -> Result<S::Ok, S::Error> {
+        MoveRepr {
+            x: self.x,
+            y: self.y,
+            rotation: self.r,
+            hold: self.held(),
+            keys: self.parse_list().map_err(serde::ser::Error::custom)?,
+            kick: self.kick,
+        }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Move {
+    fn deserialize<D: Deserializer<'de>> (deserializer: D) -> Result<Self, D::Error> {
+        let repr = MoveRepr::deserialize(deserializer)?;
+        let mut keys = repr.keys;
+        match (repr.hold, keys.first() == Some(&Key::Hold)) {
+            (true, false) => keys.insert(0, Key::Hold),
+            (false, true) => return Err(D::Error::custom("keys start with hold, but hold is false")),
+            _ => (),
+        }
+        if keys.len() as u64 > Move::LIST_CAPACITY {
+            return Err(D::Error::custom(format!("more than {} keys", Move::LIST_CAPACITY)));
+        }
+
+        let mut mov = Move { x: repr.x, y: repr.y, r: repr.rotation, list: 0, kick: repr.kick };
+        for key in &keys {
+            mov.add_key(key);
+        }
+        Ok(mov)
+    }
+}
+
+
+/// `State` as written out. The board is a list of rows, bottom first, of `#` and `.`.
+/// Empty rows above the stack are left out.
+#[derive(Serialize, Deserialize)]
+struct StateRepr {
+    board: Vec<String>,
+    queue: Vec<Piece>,
+    hold: Option<Piece>,
+    b2b: u8,
+    combo: u8,
+    #[serde(default)]
+    rules: Rules,
+    #[serde(default)]
+    garbage: Vec<Garbage>,
+}
+
+impl Board {
+    fn to_rows (&self) -> Vec<String> {
+        let height = self.v.iter().map(|col| 64 - col.leading_zeros() as usize).max().unwrap_or(0);
+        (0..height)
+            .map(|y| (0..10).map(|x| if self.v[x] & (1 << y) != 0 { '#' } else { '.' }).collect())
+            .collect()
+    }
+
+    fn from_rows (rows: &[String]) -> Result<Self, String> {
+        if rows.len() > BOARD_HEIGHT {
+            return Err(format!("board has {} rows, more than {BOARD_HEIGHT}", rows.len()));
+        }
+        let mut v = [0; 10];
+        for (y, row) in rows.iter().enumerate() {
+            if row.chars().count() != 10 {
+                return Err(format!("row {y} is not 10 cells wide: {row:?}"));
+            }
+            for (x, ch) in row.chars().enumerate() {
+                match ch {
+                    '#' => v[x] |= 1 << y,
+                    '.' => (),
+                    _ => return Err(format!("row {y} has cell {ch:?}, expected '#' or '.'")),
+                }
+            }
+        }
+        Ok(Board { v })
+    }
+}
+
+impl Serialize for State {
+    fn serialize<S: Serializer> (&self, serializer: S) -> Result<S::Ok, S::Error> {
+        StateRepr {
+            board: self.board.to_rows(),
+            queue: self.queue.iter().copied().collect(),
+            hold: self.hold,
+            b2b: self.b2b,
+            combo: self.combo,
+            rules: self.rules,
+            garbage: self.garbage.iter().copied().collect(),
+        }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for State {
+    fn deserialize<D: Deserializer<'de>> (deserializer: D) -> Result<Self, D::Error> {
+        let repr = StateRepr::deserialize(deserializer)?;
+        Ok(State {
+            board: Board::from_rows(&repr.board).map_err(D::Error::custom)?,
+            queue: repr.queue.into(),
+            hold: repr.hold,
+            b2b: repr.b2b,
+            combo: repr.combo,
+            rules: repr.rules,
+            garbage: repr.garbage.into(),
+        })
+    }
+}
@@ -0,0 +1,71 @@
+use super::*;
+use super::movegen::gen_moves;
+use serde_json::json;
+
+fn state () -> State {
+    let mut state = State::from_str("
+        . . . . . . . . . .  b2b:    2
+        . . . . . . . . . .  combo:  1
+        . . . . . . . . . .
+        . . . . . . . . . .  hold:  I
+        . . . . . . . . . .  queue:
+        . . . . . . . . . .  T
+        . . . . . . . . . .  S
+        . . . . . . . . . .  O
+        . . . . . . . . . .
+        . . . . . . . . . .
+        . . . . . . . . . .
+        . . . . . . . . . .
+        . . . . . . . . . .
+        . . . . . . . . . .
+        . . . . . . . . . .
+        . . . . . . . . . .
+        . . . . . . . . . .
+        # . . . . . . . . .
+        # # . . . . # # # #
+        # # # . # # # # # #
+    ").with_rules(Rules { spins: SpinRules::AllMini, ..Default::default() });
+    state.push_garbage(Garbage { lines: 3, col: 4, delay: 1 });
+    state
+}
+
+#[test]
+fn state_round_trip () {
+    let state = state();
+    let value = serde_json::to_value(&state).unwrap();
+    assert_eq!(value["board"], json!(["###.######", "##....####", "#........."]));
+    assert_eq!(value["queue"], json!(["T", "S", "O"]));
+    assert_eq!(value["hold"], json!("I"));
+
+    let back: State = serde_json::from_value(value).unwrap();
+    assert!(back == state);
+}
+
+#[test]
+fn move_round_trip () {
+    let state = state();
+    let moves = gen_moves(&state);
+    assert!(moves.iter().any(|mov| mov.held()));
+
+    for mov in moves {
+        let value = serde_json::to_value(&mov).unwrap();
+        assert_eq!(value["hold"], json!(mov.held()));
+
+        let back: Move = serde_json::from_value(value).unwrap();
+        assert_eq!(back.parse_list(), mov.parse_list());
+        assert_eq!((back.x, back.y, back.r, back.kick), (mov.x, mov.y, mov.r, mov.kick));
+    }
+}
+
+#[test]
+fn malformed () {
+    // hold implies a leading Hold key
+    let mov: Move = serde_json::from_value(json!({ "x": 4, "y": 1, "rotation": "N", "hold": true, "keys": ["Drop"] })).unwrap();
+    assert_eq!(mov.parse_list(), Ok(vec![Key::Hold, Key::Drop]));
+
+    let mov = serde_json::from_value::<Move>(json!({ "x": 4, "y": 1, "rotation": "N", "hold": false, "keys": ["Hold"] }));
+    assert!(mov.is_err());
+
+    let state = serde_json::from_value::<State>(json!({ "board": ["####"], "queue": [], "hold": null, "b2b": 0, "combo": 0 }));
+    assert!(state.is_err());
+}
@@ -0,0 +1,190 @@
+#[cfg(test)]
+mod tests;
+
+use super::*;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+
+/// Rules a `State` is played under. Children inherit their parent's rules.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Rules {
+    pub attack: AttackRules,
+    pub spins: SpinRules,
+    /// Whether 180 rotations are allowed.
+    pub flip: bool,
+}
+
+/// Which pieces can spin. T-spins always use the 3-corner rule; other pieces spin when immobile
+/// after a rotation.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SpinRules {
+    /// Only T-spins.
+    #[default]
+    TOnly,
+    /// Non-T spins count as minis (TETR.IO all-mini).
+    AllMini,
+    /// Non-T spins count as full spins (TETR.IO all-spin).
+    AllSpin,
+}
+
+/// Attack tables. Determines lines sent for a clear.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AttackRules {
+    /// TETR.IO Season 1: b2b chaining levels, combo multiplier, 10 line all clear.
+    #[default]
+    Tetrio,
+    /// TETR.IO Season 2: flat b2b bonus, surge on breaking a b2b chain, 3 line all clear.
+    TetrioS2,
+    /// Jstris: flat b2b bonus, combo table, 10 line all clear.
+    Jstris,
+}
+
+/// Describes a placement, as needed to calculate its attack.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Clear {
+    pub lines: u32,
+    pub spin: Spin,
+    /// Length of the b2b chain including this clear. 0 if this clear broke or has no chain.
+    pub b2b: u8,
+    /// Length of the b2b chain before this placement.
+    pub prev_b2b: u8,
+    /// Consecutive clears including this one.
+    pub combo: u8,
+    pub all_clear: bool,
+}
+
+impl Clear {
+    /// Whether the clear continues a b2b chain: quads and spins.
+    pub fn is_difficult (lines: u32, spin: Spin) -> bool {
+        lines == 4 || (lines > 0 && spin != Spin::None)
+    }
+}
+
+/// Jstris combo bonus, indexed by combo (first clear being 0).
+const JSTRIS_COMBO: [u8; 13] = [0, 0, 1, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5];
+
+/// B2B chains this long or longer release a surge when broken (TETR.IO S2).
+const SURGE_THRESHOLD: u8 = 4;
+
+impl AttackRules {
+    /// Lines sent for a placement.
+    pub fn attack (&self, clear: &Clear) -> u8 {
+        if clear.lines == 0 {
+            return 0;
+        }
+
+        // B2B bonus applies from the second clear of a chain. Combo counts from the first clear.
+        let btb = clear.b2b.saturating_sub(1) as u32;
+        let combo = clear.combo.saturating_sub(1) as u32;
+        let base = Self::base(clear.lines, clear.spin);
+
+        let attack = match self {
+            AttackRules::Tetrio => Self::tetrio(base, clear, btb, combo),
+            AttackRules::TetrioS2 => {
+                let bonus = if btb > 0 { 1 } else { 0 };
+                let surge =
+                    if clear.b2b == 0 && clear.prev_b2b >= SURGE_THRESHOLD { clear.prev_b2b as u32 - 1 }
+                    else { 0 };
+                Self::combo_multiplier(base + bonus, combo) + surge
+            },
+            AttackRules::Jstris => {
+                let bonus = if btb > 0 { 1 } else { 0 };
+                base + bonus + JSTRIS_COMBO[(combo as usize).min(JSTRIS_COMBO.len() - 1)] as u32
+            },
+        };
+
+        let all_clear = if clear.all_clear {
+            match self {
+                AttackRules::TetrioS2 => 3,
+                _ => 10,
+            }
+        } else { 0 };
+
+        (attack + all_clear).min(u8::MAX as u32) as u8
+    }
+
+    /// Lines sent by a clear without any bonuses.
+    fn base (lines: u32, spin: Spin) -> u32 {
+        match spin {
+            Spin::None => match lines {
+                0 | 1 => 0,
+                2 => 1,
+                3 => 2,
+                _ => 4,
+            },
+            Spin::Mini => lines.saturating_sub(1),
+            Spin::Full => lines * 2,
+        }
+    }
+
+    /// TETR.IO S1. Tabled for quads and T-spins, otherwise calculated the same way.
+    fn tetrio (base: u32, clear: &Clear, btb: u32, combo: u32) -> u32 {
+        let level = Self::tetrio_b2b_level(btb);
+
+        let index = match (clear.spin, clear.lines) {
+            (Spin::None, 4) => Some(0),
+            (Spin::Full, lines @ 1..=3) => Some(lines as usize),
+            _ => None,
+        };
+
+        match index {
+            Some(index) if (level as usize) < B2B_TABLE.len() && (combo as usize) < B2B_TABLE[0][0].len() =>
+                B2B_TABLE[level as usize][index][combo as usize],
+            _ => Self::combo_multiplier(base + level, combo),
+        }
+    }
+
+    /// B2B chain bonus. +1 for 1-2, +2 for 3-7, +3 for 8-23, ...
+    fn tetrio_b2b_level (btb: u32) -> u32 {
+        if btb == 0 {
+            0
+        } else {
+            (1.0 + (btb as f32 * 0.8).ln_1p()).floor() as u32
+        }
+    }
+
+    /// TETR.IO combo: +25% per combo. Long combos send at least `ln(1 + 1.25 * combo)`.
+    fn combo_multiplier (attack: u32, combo: u32) -> u32 {
+        let multiplied = attack as f32 * (1.0 + 0.25 * combo as f32);
+        let attack = if combo > 1 {
+            multiplied.max((1.25 * combo as f32).ln_1p())
+        } else {
+            multiplied
+        };
+        attack.floor() as u32
+    }
+}
+
+
+/// Tetr.io garbage table, indexed by b2b level, clear (quad, TSS, TSD, TST), then combo.
+pub const B2B_TABLE: [[[u32; 10]; 4]; 4] = [
+    [
+        [4, 5, 6, 7, 8, 9, 10, 11, 12, 13],
+        [2, 2, 3, 3, 4, 4, 5, 5, 6, 6],
+        [4, 5, 6, 7, 8, 9, 10, 11, 12, 13],
+        [6, 7, 9, 10, 12, 13, 15, 16, 18, 19],
+    ],
+    [
+        [5, 6, 7, 8, 10, 11, 12, 13, 15, 16],
+        [3, 3, 4, 5, 6, 6, 7, 8, 9, 9],
+        [5, 6, 7, 8, 10, 11, 12, 13, 15, 16],
+        [7, 8, 10, 12, 14, 15, 17, 19, 21, 22],
+    ],
+    [
+        [6, 7, 9, 10, 12, 13, 15, 16, 18, 19],
+        [4, 5, 6, 7, 8, 9, 10, 11, 12, 13],
+        [6, 7, 9, 10, 12, 13, 15, 16, 18, 19],
+        [8, 10, 12, 14, 16, 18, 20, 22, 24, 25],
+    ],
+    [
+        [7, 8, 10, 12, 14, 15, 17, 19, 21, 22],
+        [5, 6, 7, 8, 10, 11, 12, 13, 15, 16],
+        [7, 8, 10, 12, 14, 15, 17, 19, 21, 22],
+        [9, 11, 13, 15, 18, 20, 22, 24, 27, 29],
+    ],
+];
@@ -0,0 +1,64 @@
+use super::*;
+
+fn clear (lines: u32, spin: Spin, b2b: u8, combo: u8) -> Clear {
+    Clear { lines, spin, b2b, prev_b2b: b2b.saturating_sub(1), combo, all_clear: false }
+}
+
+#[test]
+fn tetrio () {
+    let rules = AttackRules::Tetrio;
+
+    // Opening quad & TSD, no bonuses
+    assert_eq!(rules.attack(&clear(4, Spin::None, 1, 1)), 4);
+    assert_eq!(rules.attack(&clear(2, Spin::Full, 1, 1)), 4);
+
+    // B2B levels
+    assert_eq!(rules.attack(&clear(2, Spin::Full, 2, 1)), 5);
+    assert_eq!(rules.attack(&clear(2, Spin::Full, 4, 1)), 6);
+    assert_eq!(rules.attack(&clear(4, Spin::None, 9, 1)), 7);
+
+    // Combo
+    assert_eq!(rules.attack(&clear(1, Spin::None, 0, 1)), 0);
+    assert_eq!(rules.attack(&clear(1, Spin::None, 0, 3)), 1);
+    assert_eq!(rules.attack(&clear(2, Spin::None, 0, 5)), 2);
+    assert_eq!(rules.attack(&clear(3, Spin::Full, 2, 4)), 12);
+
+    // Minis
+    assert_eq!(rules.attack(&clear(1, Spin::Mini, 1, 1)), 0);
+    assert_eq!(rules.attack(&clear(2, Spin::Mini, 1, 1)), 1);
+
+    // All clear
+    let pc = Clear { all_clear: true, ..clear(4, Spin::None, 1, 1) };
+    assert_eq!(rules.attack(&pc), 14);
+}
+
+#[test]
+fn tetrio_s2 () {
+    let rules = AttackRules::TetrioS2;
+
+    // Flat b2b bonus
+    assert_eq!(rules.attack(&clear(4, Spin::None, 9, 1)), 5);
+
+    // Breaking a chain of 6 with a single surges 5
+    let broken = Clear { lines: 1, spin: Spin::None, b2b: 0, prev_b2b: 6, combo: 1, all_clear: false };
+    assert_eq!(rules.attack(&broken), 5);
+
+    let pc = Clear { all_clear: true, ..clear(2, Spin::None, 0, 1) };
+    assert_eq!(rules.attack(&pc), 4);
+}
+
+#[test]
+fn jstris () {
+    let rules = AttackRules::Jstris;
+
+    assert_eq!(rules.attack(&clear(4, Spin::None, 2, 1)), 5);
+    assert_eq!(rules.attack(&clear(1, Spin::None, 0, 6)), 2);
+    assert_eq!(rules.attack(&clear(1, Spin::None, 0, 50)), 5);
+}
+
+#[test]
+fn no_clear () {
+    for rules in [AttackRules::Tetrio, AttackRules::TetrioS2, AttackRules::Jstris] {
+        assert_eq!(rules.attack(&clear(0, Spin::Full, 5, 0)), 0);
+    }
+}
@@ -1,5 +1,13 @@
+#[cfg(test)]
+mod tests;
+
 use super::*;
 
+mod randomizer;
+
+pub use randomizer::Randomizer;
+use randomizer::Generator;
+
 
 pub const RST: &str = "\x1b[0m";
 pub const BLD: &str = "\x1b[1m";
@@ -22,16 +30,19 @@ impl Piece {
 
 pub struct SimState {
     pub state: State,
-    v: [[Piece; 10]; 20],
-    bag: Vec<Piece>,
+    v: [[Piece; 10]; BOARD_HEIGHT],
+    generator: Generator,
+    /// Rows cleared by the last placement. Bit `y` is row `y` of the board before the clear.
+    cleared: u64,
 }
 
 impl std::fmt::Display for SimState {
     fn fmt(self: &Self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        for y in 0..20 {
+        let height = self.state.board.display_height();
+        for y in 0..height {
             for x in 0..10 {
-                if self.v[19-y][x] != Piece::None {
-                    let c = self.v[19-y][x].color();
+                if self.v[height-1-y][x] != Piece::None {
+                    let c = self.v[height-1-y][x].color();
                     write!(f, "{}  {}", c, RST)?;
                 } else {
                     write!(f, ". ")?;
@@ -58,33 +69,65 @@ impl std::fmt::Display for SimState {
 
 
 impl SimState {
+    /// Creates a game of 7-bag, seeded at random.
     pub fn new () -> Self {
+        Self::seeded(Randomizer::default(), rand::random())
+    }
+
+    /// Creates a game whose pieces are fully determined by the seed.
+    pub fn seeded (randomizer: Randomizer, seed: u64) -> Self {
         let mut out = Self {
-            v: [[Piece::None; 10]; 20],
-            bag: vec![],
+            v: [[Piece::None; 10]; BOARD_HEIGHT],
+            generator: Generator::new(randomizer, seed),
+            cleared: 0,
             state: Default::default()
         };
         out.draw();
         out
     }
 
+    /// Sets the rules the game is played under.
+    pub fn with_rules (mut self, rules: Rules) -> Self {
+        self.state = self.state.with_rules(rules);
+        self
+    }
+
     pub fn get_state (&self) -> &State {
         &self.state
     }
 
+    pub fn randomizer (&self) -> Randomizer {
+        self.generator.randomizer()
+    }
+
+    pub fn seed (&self) -> u64 {
+        self.generator.seed()
+    }
+
+    /// Rows cleared by the last placement. Bit `y` is row `y` of the board before the clear.
+    pub fn cleared (&self) -> u64 {
+        self.cleared
+    }
+
     /// Creates SimState from textual representation.
     pub fn from_str (s: &str) -> Self {
-        let s = s.trim();
+        Self::from_state(State::from_str(s.trim()))
+    }
 
-        let state = State::from_str(s);
-        let bag = vec![Piece::J, Piece::L, Piece::S, Piece::Z, Piece::T, Piece::I, Piece::O]
-            .into_iter()
-            .filter(|piece| !state.queue.contains(piece))
-            .collect();
-        let mut v = [[Piece::None; 10]; 20];
+    /// Creates SimState continuing from a state, as a 7-bag seeded at random.
+    /// Blocks already on the board are colored as garbage.
+    pub fn from_state (state: State) -> Self {
+        let mut generator = Generator::new(Randomizer::Bag7, rand::random());
+        generator.resume(
+            vec![Piece::J, Piece::L, Piece::S, Piece::Z, Piece::T, Piece::I, Piece::O]
+                .into_iter()
+                .filter(|piece| !state.queue.contains(piece))
+                .collect()
+        );
+        let mut v = [[Piece::None; 10]; BOARD_HEIGHT];
         
         for x in 0..10 {
-            for y in 0..20 {
+            for y in 0..BOARD_HEIGHT {
                 if state.board.v[x] & 1 << y != 0 {
                     v[y][x] = Piece::O;
                 }
@@ -94,7 +137,8 @@ impl SimState {
         Self {
             state,
             v,
-            bag,
+            generator,
+            cleared: 0,
         }
     }
 
@@ -104,7 +148,7 @@ impl SimState {
         let board = Board::from_str(s);
 
         for x in 0..10 {
-            for y in 0..20 {
+            for y in 0..BOARD_HEIGHT {
                 if board.v[x] & 1 << y != 0 && self.v[y][x] == Piece::None {
                     return false;
                 }
@@ -113,15 +157,11 @@ impl SimState {
         return true;
     }
 
-    /// Draws from bag, populates piece queue. Auto re-fill of bag.
+    /// Populates the piece queue from the generator.
     pub fn draw (&mut self) {
         while self.state.queue.len() < 6 {
-            if self.bag.is_empty() {
-                self.bag = vec![Piece::J, Piece::L, Piece::S, Piece::Z, Piece::T, Piece::I, Piece::O];
-            }
-            let i = rand::random::<usize>().clamp(0, self.bag.len()-1);
-            let p = self.bag.remove(i);
-            self.state.queue.push_back(p);
+            let piece = self.generator.next();
+            self.state.queue.push_back(piece);
         }
     }
 
@@ -131,25 +171,61 @@ impl SimState {
         R: rand::Rng 
     {
         let lines = lines.min(10);
+        let col = rand.gen_range(0..10);
+
+        self.state.board.add_garbage(lines as u8, col);
+        self.raise(lines);
+    }
 
-        let i = rand.gen_range(0..9);
-        let mut garbage_row = [Piece::O; 10];
-        garbage_row[i as usize] = Piece::None;
+    /// Queues garbage lines onto the state. With a `delay` of 0, the next placement can cancel it,
+    /// and lets it in unless it clears. See `Garbage::delay`.
+    /// Returns the garbage queued.
+    pub fn queue_garbage<R> (&mut self, lines: usize, delay: u8, rand: &mut R) -> Garbage
+    where
+        R: rand::Rng
+    {
+        let garbage = Garbage {
+            lines: lines.min(u8::MAX as usize) as u8,
+            col: rand.gen_range(0..10),
+            delay,
+        };
+        self.state.push_garbage(garbage);
+        garbage
+    }
 
-        for y in (0..20).rev() {
-            if y < lines {
-                self.v[y] = garbage_row;
-            } else {
-                self.v[y] = self.v[y-lines];
-            }
+    /// Generates cheese: garbage rows of one hole each, no hole right above another.
+    pub fn gen_cheese<R> (&mut self, lines: usize, rand: &mut R)
+    where
+        R: rand::Rng
+    {
+        // Hole of the bottom row, if it is a garbage row.
+        let holes: Vec<u8> = (0..10).filter(|x| self.state.board.v[*x as usize] & 1 == 0).collect();
+        let mut last = if holes.len() == 1 { Some(holes[0]) } else { None };
+
+        for _ in 0..lines.min(BOARD_HEIGHT) {
+            let col = loop {
+                let col = rand.gen_range(0..10);
+                if Some(col) != last {
+                    break col;
+                }
+            };
+            self.state.board.add_garbage(1, col);
+            self.raise(1);
+            last = Some(col);
         }
+    }
 
-        for x in 0..10 {
-            if x == i as usize {
-                self.state.board.v[x] <<= lines;
+    /// Raises the colored board by `lines`, copying the new bottom rows from the state's board.
+    fn raise (&mut self, lines: usize) {
+        let lines = lines.min(BOARD_HEIGHT);
+        for y in (0..BOARD_HEIGHT).rev() {
+            if y < lines {
+                for x in 0..10 {
+                    let filled = self.state.board.v[x] & 1 << y != 0;
+                    self.v[y][x] = if filled { Piece::O } else { Piece::None };
+                }
             } else {
-                // We want the bits added by the shift to be ones, thus the double negation.
-                self.state.board.v[x] = !(!self.state.board.v[x] << lines);
+                self.v[y] = self.v[y-lines];
             }
         }
     }
@@ -177,9 +253,11 @@ impl SimState {
         }
         // Clear lines for colored V 
         let mut clears = 0;
-        for y in 0..20 {
+        self.cleared = 0;
+        for y in 0..BOARD_HEIGHT {
             if self.v[y].iter().fold(true, |a, cell| a && *cell != Piece::None) {
                 clears += 1;
+                self.cleared |= 1 << y;
             } else if clears != 0 {
                 self.v[y-clears] = self.v[y];
                 self.v[y] = [Piece::None; 10];
@@ -189,6 +267,7 @@ impl SimState {
         // Apply onto State, get MoveStats
         let (state, move_stats) = self.state.apply_move_with_stats(mov);
         self.state = state;
+        self.raise(move_stats.received as usize);
 
 
         self.draw();
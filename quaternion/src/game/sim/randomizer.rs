@@ -0,0 +1,211 @@
+#[cfg(test)]
+mod tests;
+
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+
+use super::*;
+
+
+/// How pieces are dealt.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Randomizer {
+    /// Each piece once per bag of 7.
+    #[default]
+    Bag7,
+    /// Each piece twice per bag of 14.
+    Bag14,
+    /// Each piece drawn independently. Allows floods and droughts.
+    Classic,
+    /// 7-bag shuffled the way TETR.IO is understood to shuffle. Not checked against recorded games.
+    Tetrio,
+    /// 7-bag drawn the way Jstris is understood to draw. Not checked against recorded games.
+    /// Jstris seeds are base 36: `1d4x9a` is seed 82530910.
+    Jstris,
+}
+
+const PIECES: [Piece; 7] = [Piece::J, Piece::L, Piece::S, Piece::Z, Piece::T, Piece::I, Piece::O];
+/// The bag TETR.IO shuffles, in its order.
+const TETRIO_PIECES: [Piece; 7] = [Piece::Z, Piece::L, Piece::O, Piece::S, Piece::I, Piece::J, Piece::T];
+/// The bag Jstris draws from, in its order.
+const JSTRIS_PIECES: [Piece; 7] = [Piece::I, Piece::O, Piece::T, Piece::L, Piece::J, Piece::S, Piece::Z];
+
+/// Deals pieces as per a randomizer. Fully determined by the seed.
+pub struct Generator {
+    randomizer: Randomizer,
+    seed: u64,
+    rng: ChaCha8Rng,
+    tetrio: ParkMiller,
+    jstris: Alea,
+    /// Pieces left in the bag, dealt from the back.
+    bag: Vec<Piece>,
+}
+
+impl Generator {
+    pub fn new (randomizer: Randomizer, seed: u64) -> Self {
+        Self {
+            randomizer,
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            tetrio: ParkMiller::new(seed),
+            jstris: Alea::new(&[&base36(seed)]),
+            bag: vec![],
+        }
+    }
+
+    pub fn randomizer (&self) -> Randomizer {
+        self.randomizer
+    }
+
+    pub fn seed (&self) -> u64 {
+        self.seed
+    }
+
+    /// Continues from a partially dealt bag.
+    pub(super) fn resume (&mut self, mut bag: Vec<Piece>) {
+        bag.shuffle(&mut self.rng);
+        self.bag = bag;
+    }
+
+    pub fn next (&mut self) -> Piece {
+        if self.randomizer == Randomizer::Classic {
+            return PIECES[self.rng.gen_range(0..PIECES.len())];
+        }
+
+        if self.bag.is_empty() {
+            self.bag = match self.randomizer {
+                Randomizer::Bag7 => PIECES.to_vec(),
+                Randomizer::Bag14 => [PIECES, PIECES].concat(),
+                Randomizer::Tetrio => TETRIO_PIECES.to_vec(),
+                Randomizer::Jstris => JSTRIS_PIECES.to_vec(),
+                Randomizer::Classic => unreachable!(),
+            };
+            match self.randomizer {
+                Randomizer::Tetrio => {
+                    self.tetrio.shuffle(&mut self.bag);
+                    // TETR.IO deals from the front.
+                    self.bag.reverse();
+                },
+                Randomizer::Jstris => {
+                    // Jstris draws each piece from what is left of the bag. Drawn ahead, in order.
+                    let mut left = std::mem::take(&mut self.bag);
+                    while !left.is_empty() {
+                        let i = (self.jstris.next() * left.len() as f64) as usize;
+                        self.bag.push(left.remove(i));
+                    }
+                    self.bag.reverse();
+                },
+                _ => self.bag.shuffle(&mut self.rng),
+            }
+        }
+        self.bag.pop().unwrap()
+    }
+}
+
+
+/// TETR.IO's Park-Miller generator.
+struct ParkMiller (u64);
+
+impl ParkMiller {
+    const M: u64 = 2147483647;
+
+    fn new (seed: u64) -> Self {
+        match seed % Self::M {
+            0 => Self(Self::M - 1),
+            t => Self(t),
+        }
+    }
+
+    fn next (&mut self) -> u64 {
+        self.0 = self.0 * 16807 % Self::M;
+        self.0
+    }
+
+    fn next_float (&mut self) -> f64 {
+        (self.next() - 1) as f64 / (Self::M - 1) as f64
+    }
+
+    /// Fisher-Yates, from the back.
+    fn shuffle<T> (&mut self, v: &mut [T]) {
+        for i in (1..v.len()).rev() {
+            let r = (self.next_float() * (i + 1) as f64) as usize;
+            v.swap(i, r);
+        }
+    }
+}
+
+
+/// Baagøe's Alea, which Jstris seeds with its seed string. Follows the JavaScript, doubles and all.
+struct Alea {
+    s0: f64,
+    s1: f64,
+    s2: f64,
+    c: f64,
+}
+
+impl Alea {
+    fn new (seeds: &[&str]) -> Self {
+        let mut mash = Mash(0xefc8249d_u32 as f64);
+        let mut s = [mash.hash(" "), mash.hash(" "), mash.hash(" ")];
+        for seed in seeds {
+            for s in s.iter_mut() {
+                *s -= mash.hash(seed);
+                if *s < 0.0 {
+                    *s += 1.0;
+                }
+            }
+        }
+        Self { s0: s[0], s1: s[1], s2: s[2], c: 1.0 }
+    }
+
+    /// Uniform in [0, 1).
+    fn next (&mut self) -> f64 {
+        let t = 2091639.0 * self.s0 + self.c * 2.3283064365386963e-10;
+        self.s0 = self.s1;
+        self.s1 = self.s2;
+        self.c = t.trunc();
+        self.s2 = t - self.c;
+        self.s2
+    }
+}
+
+/// Alea's string hash.
+struct Mash (f64);
+
+impl Mash {
+    /// `x >>> 0`
+    fn uint32 (x: f64) -> f64 {
+        x.trunc().rem_euclid(4294967296.0)
+    }
+
+    fn hash (&mut self, data: &str) -> f64 {
+        let mut n = self.0;
+        // JavaScript strings are UTF-16.
+        for unit in data.encode_utf16() {
+            n += unit as f64;
+            let mut h = 0.02519603282416938 * n;
+            n = Self::uint32(h);
+            h -= n;
+            h *= n;
+            n = Self::uint32(h);
+            h -= n;
+            n += h * 4294967296.0;
+        }
+        self.0 = n;
+        Self::uint32(n) * 2.3283064365386963e-10
+    }
+}
+
+/// `seed` in base 36, lowercase, as Jstris writes seeds.
+fn base36 (mut seed: u64) -> String {
+    let mut digits = vec![];
+    loop {
+        digits.push(std::char::from_digit((seed % 36) as u32, 36).unwrap());
+        seed /= 36;
+        if seed == 0 {
+            break;
+        }
+    }
+    digits.iter().rev().collect()
+}
@@ -0,0 +1,46 @@
+use super::*;
+
+fn deal (randomizer: Randomizer, seed: u64, n: usize) -> Vec<Piece> {
+    let mut generator = Generator::new(randomizer, seed);
+    (0..n).map(|_| generator.next()).collect()
+}
+
+#[test]
+fn park_miller () {
+    // minstd_rand0, seeded with 1.
+    let mut rng = ParkMiller::new(1);
+    let out: Vec<u64> = (0..4).map(|_| rng.next()).collect();
+    assert_eq!(out, [16807, 282475249, 1622650073, 984943658]);
+}
+
+#[test]
+fn alea () {
+    // Baagøe's example.
+    let mut rng = Alea::new(&["my", "3", "seeds"]);
+    let out: Vec<f64> = (0..3).map(|_| rng.next()).collect();
+    assert_eq!(out, [0.30802189325913787, 0.5190450621303171, 0.43635262292809784]);
+
+    assert_eq!(base36(82530910), "1d4x9a");
+    assert_eq!(base36(0), "0");
+}
+
+#[test]
+fn bags () {
+    for (randomizer, size) in [(Randomizer::Bag7, 7), (Randomizer::Bag14, 14), (Randomizer::Tetrio, 7), (Randomizer::Jstris, 7)] {
+        let pieces = deal(randomizer, 7, size * 5);
+        for bag in pieces.chunks(size) {
+            for piece in PIECES {
+                assert_eq!(bag.iter().filter(|p| **p == piece).count(), size / 7, "{randomizer:?}: {bag:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn seeded () {
+    for randomizer in [Randomizer::Bag7, Randomizer::Bag14, Randomizer::Classic, Randomizer::Tetrio, Randomizer::Jstris] {
+        assert_eq!(deal(randomizer, 3, 50), deal(randomizer, 3, 50));
+        assert_ne!(deal(randomizer, 3, 50), deal(randomizer, 4, 50));
+    }
+    assert!(deal(Randomizer::Classic, 3, 50).iter().all(|p| *p != Piece::None));
+}
@@ -0,0 +1,55 @@
+use super::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+#[test]
+fn cheese () {
+    let mut sim = SimState::seeded(Randomizer::Bag7, 0);
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    sim.gen_cheese(6, &mut rng);
+    sim.gen_cheese(6, &mut rng);
+
+    let holes: Vec<usize> = (0..12)
+        .map(|y| {
+            let row: Vec<usize> = (0..10).filter(|x| sim.state.board.v[*x] & 1 << y == 0).collect();
+            assert_eq!(row.len(), 1, "row {y} has holes {row:?}");
+            row[0]
+        })
+        .collect();
+    assert!(holes.windows(2).all(|w| w[0] != w[1]), "{holes:?}");
+    assert!(sim.state.board.v.iter().all(|col| col >> 12 == 0));
+}
+
+#[test]
+fn cleared () {
+    let sim = SimState::from_str("
+        . . . . . . . . . .  b2b:    0
+        . . . . . . . . . .  combo:  0
+        . . . . . . . . . .
+        . . . . . . . . . .  hold:  none
+        . . . . . . . . . .  queue:
+        . . . . . . . . . .  O
+        . . . . . . . . . .
+        . . . . . . . . . .
+        . . . . . . . . . .
+        . . . . . . . . . .
+        . . . . . . . . . .
+        . . . . . . . . . .
+        . . . . . . . . . .
+        . . . . . . . . . .
+        . . . . . . . . . .
+        . . . . . . . . . .
+        . . . . . . . . . .
+        # # # # # # # # . .
+        # # # # # # # # . .
+        # # # # # # # . . #
+    ");
+    let mov = sim.get_state()
+        .legal_moves()
+        .into_iter()
+        .find(|mov| mov.x() == 8 && mov.y() == 1)
+        .expect("O fits the gap");
+    let (sim, stats) = sim.advance(&mov);
+    assert_eq!(stats.ds, 2);
+    assert_eq!(sim.cleared(), 0b110);
+}
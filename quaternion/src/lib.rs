@@ -1,22 +1,36 @@
 mod game;
 mod tree;
 mod worker;
+mod clock;
+mod error;
 
 // Re-Exports (for driver)
-pub use game::{Piece, Key, State, Move};
+pub use error::{QuaternionError, ReplayError};
+pub use game::{Piece, Key, Rotation, Spin, State, Move, Garbage};
+pub use game::VISIBLE_HEIGHT;
+pub use game::{Rules, AttackRules, SpinRules};
 pub use worker::BotStats;
 pub use game::MoveStats;
+pub use game::eval::{EvalConfig, Weights, Factors};
+pub use tree::{PrincipalVariation, Planned};
+pub use tree::policy::{Policy, Candidate, MinExpansions, Uct, Rank};
 
 // For single-threaded WASM driver
 pub use worker::Worker;
 
+// For benchmarks
+pub mod bench;
+
 #[cfg(not(target_family = "wasm"))]
-pub use game::sim::SimState;
+pub use game::sim::{SimState, Randomizer};
 
 use std::sync::Arc;
+use std::time::Duration;
 
 #[cfg(not(target_family = "wasm"))]
 use std::thread;
+#[cfg(not(target_family = "wasm"))]
+use std::time::Instant;
 
 
 
@@ -29,29 +43,124 @@ pub struct Quaternion {
 
 
 impl Quaternion {
-    pub fn start (&self) { 
-        self.worker.start(&mut self.worker.state.lock());
+    /// Errs if already running.
+    pub fn start (&self) -> Result<(), QuaternionError> {
+        self.worker.start(&mut self.worker.state.lock())
     }
 
-    pub fn stop (&self) {
-        self.worker.stop(&mut self.worker.state.lock());
+    /// Errs if not running.
+    pub fn stop (&self) -> Result<(), QuaternionError> {
+        self.worker.stop(&mut self.worker.state.lock())
     }
 
-    pub fn solution (&self) -> Move {
+    /// The best move found so far. Errs with `ToppedOut` if there is no legal placement, and with
+    /// `NoSolution` if the state was not searched yet.
+    pub fn solution (&self) -> Result<Move, QuaternionError> {
         self.worker
             .solution()
-            .expect("worker.solution() returned Err")
-            .mv
+            .map(|child| child.mv)
+    }
+
+    /// The line the bot currently plans, with the evaluation and stats of each placement, and the
+    /// state it ends in. Empty until the root is expanded.
+    pub fn principal_variation (&self) -> PrincipalVariation {
+        self.worker.principal_variation()
     }
 
     /// Exposed interface for bot advancement.
     /// Takes in a `SimState`, converts it into a `State`, and passes it to the worker.
-    pub fn advance (&self, state: &State) {
-        self.worker.advance(&state);
+    /// Errs with `EmptyQueue` on a state without pieces, leaving the bot as it was.
+    pub fn advance (&self, state: &State) -> Result<(), QuaternionError> {
+        self.worker.advance(state)
     }
 
     pub fn stats (&self) -> BotStats { 
-        self.worker.state.lock().stats.clone()
+        self.worker.stats()
+    }
+
+    /// Searches for the given duration, then returns the best move.
+    /// Returns early if the tree fills up, or has nothing left to search. Starts the bot if it is not
+    /// running. Workers idle once the time is up, until the next `advance(..)`.
+    /// Errs as `solution()` does.
+    pub fn think_for (&self, duration: Duration) -> Result<Move, QuaternionError> {
+        let deadline = clock::now() + duration.as_secs_f64() * 1000.0;
+        let mut state = self.worker.state.lock();
+        self.worker.set_deadline(&mut state, Some(deadline));
+        if !state.run {
+            self.worker.start(&mut state)?;
+        }
+
+        if self.threaded() {
+            #[cfg(not(target_family = "wasm"))]
+            {
+                let end = Instant::now() + duration;
+                while state.should_work() {
+                    if self.worker.idle.wait_until(&mut state, end).timed_out() {
+                        break;
+                    }
+                }
+            }
+        } else {
+            // No worker threads: do the work on this one.
+            while state.should_work() {
+                parking_lot::MutexGuard::unlocked(&mut state, || self.worker.work());
+            }
+        }
+        drop(state);
+
+        self.solution()
+    }
+
+    /// Searches until `nodes` nodes were generated for the current state, then returns the best move.
+    /// Returns early if the tree fills up, or has nothing left to search. Starts the bot if it is not
+    /// running. Workers idle once the budget is spent, until the next `advance(..)`.
+    /// Errs as `solution()` does.
+    pub fn think_nodes (&self, nodes: u64) -> Result<Move, QuaternionError> {
+        let mut state = self.worker.state.lock();
+        self.worker.set_deadline(&mut state, None);
+        self.worker.set_node_budget(&mut state, Some(nodes));
+        if !state.run {
+            self.worker.start(&mut state)?;
+        }
+
+        if self.threaded() {
+            while state.should_work() {
+                self.worker.idle.wait(&mut state);
+            }
+        } else {
+            // No worker threads: do the work on this one.
+            while state.should_work() {
+                parking_lot::MutexGuard::unlocked(&mut state, || self.worker.work());
+            }
+        }
+        drop(state);
+
+        self.solution()
+    }
+
+    pub fn builder () -> Builder {
+        Builder::default()
+    }
+
+    /// Does not spawn threads. Work is done by the caller, through `think_for(..)`.
+    /// Used by the WASM driver.
+    pub fn single () -> Self {
+        Self::builder().threads(0).build()
+    }
+
+    // For WASM driver. A single-threaded version.
+    pub fn wasm_run (&self, delay_ms: u32) -> Result<Move, QuaternionError> {
+        self.think_for(Duration::from_millis(delay_ms as u64))
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    fn threaded (&self) -> bool {
+        !self.handles.is_empty()
+    }
+
+    #[cfg(target_family = "wasm")]
+    fn threaded (&self) -> bool {
+        false
     }
 }
 
@@ -59,58 +168,93 @@ impl Quaternion {
 impl Quaternion {
     #[cfg(not(target_family = "wasm"))]
     pub fn with_threads(threads: u32) -> Self {
+        Self::builder().threads(threads).build()
+    }
+
+    /// Searches until the deadline, then returns the best move. See `think_for(..)`.
+    pub fn solution_within (&self, deadline: Instant) -> Result<Move, QuaternionError> {
+        self.think_for(deadline.saturating_duration_since(Instant::now()))
+    }
+
+    pub fn end (self) {
+        let _ = self.stop();
+        self.handles.into_iter().for_each(|handle| handle.join().expect("failed to join worker"));
+    }
+}
+
+
+
+/// Configures a `Quaternion` before its workers are spawned.
+#[derive(Clone)]
+pub struct Builder {
+    #[cfg_attr(target_family = "wasm", allow(dead_code))]
+    threads: u32,
+    eval: EvalConfig,
+    capacity: usize,
+    policy: Arc<dyn Policy>,
+}
+
+impl Default for Builder {
+    fn default () -> Self {
+        Self {
+            threads: 8,
+            eval: EvalConfig::default(),
+            capacity: tree::CAPACITY,
+            policy: Arc::new(MinExpansions),
+        }
+    }
+}
+
+impl Builder {
+    /// Worker threads to spawn. With 0, work is done by the caller through `think_for(..)`.
+    /// Ignored on WASM, which is always single-threaded.
+    pub fn threads (mut self, threads: u32) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Evaluation parameters of this bot.
+    pub fn eval (mut self, eval: EvalConfig) -> Self {
+        self.eval = eval;
+        self
+    }
+
+    /// Most nodes the search tree holds. Memory is allocated as the tree grows, up to a ceiling
+    /// proportional to this. Once full, the bot stops searching until the next `advance(..)`.
+    /// At least 1, for the root.
+    pub fn capacity (mut self, nodes: usize) -> Self {
+        self.capacity = nodes;
+        self
+    }
+
+    /// Selects the nodes to expand. Defaults to `MinExpansions`.
+    pub fn policy (mut self, policy: impl Policy + 'static) -> Self {
+        self.policy = Arc::new(policy);
+        self
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    pub fn build (self) -> Quaternion {
         // Spawn in worker threads.
-        let worker = Arc::new(worker::Worker::new());
-        
+        let worker = Arc::new(worker::Worker::with_config(self.eval, self.capacity, self.policy));
+
         let handles: Vec<_> = 
-            (0..threads)
+            (0..self.threads)
             .map(|_| {
                 let worker = worker.clone();
                 thread::spawn(move || { worker.work_loop() })
             })
             .collect(); 
-        Self {
+        Quaternion {
             worker,
             handles
         }
     }
 
-    pub fn end (self) {
-        self.stop();
-        self.handles.into_iter().for_each(|handle| handle.join().expect("failed to join worker"));
-    }
-}
-
-
-#[cfg(target_family = "wasm")]
-use std::time::Duration;
-#[cfg(target_family = "wasm")]
-impl Quaternion {
-    // For WASM driver. Does not spawn threads
-    pub fn single () -> Self {
-        let worker = Arc::new(worker::Worker::new());
-        
-        Self { worker }
-    }
-
-    // For WASM driver. A single-threaded version.
-    pub fn wasm_run (&self, delay_ms: u32) -> Move {
-
-        // Get timestamp
-        /*
-        let window = web_sys::window().expect("should have a window in this context");
-        let performance = window
-            .performance()
-            .expect("performance should be available");
-        let start = performance.now();
-        */
-
-        self.start();
-        //while start < delay_ms as f64 {
-        for _ in 0..1000 {
-            self.worker.work();
+    #[cfg(target_family = "wasm")]
+    pub fn build (self) -> Quaternion {
+        Quaternion {
+            worker: Arc::new(worker::Worker::with_config(self.eval, self.capacity, self.policy)),
         }
-        self.stop();
-        self.solution()
     }
 }
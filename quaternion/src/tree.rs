@@ -1,116 +1,284 @@
-use std::sync::Arc; 
+#[cfg(test)]
+mod tests;
+
+mod arena;
+pub mod policy;
+
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry;
+use std::sync::{Arc, OnceLock};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use parking_lot::{Mutex, RwLock};
 use super::game;
+use super::QuaternionError;
+use arena::Arena;
+use policy::{Candidate, Policy};
 
 const CUTOFF_F: f32 = 0.2;
 
+/// Nodes a tree holds by default. Children get twice as many slots, as merged nodes have several
+/// parents.
+pub const CAPACITY: usize = 1 << 20;
+
+/*
+ * The tree is a DAG: move orders reaching the same state share one `Node`, found through the
+ * transposition table. A `Node` holds what belongs to the state (its children, and the best
+ * evaluation found below it). A `Child` holds what belongs to the move into it (the move, and the
+ * evaluation of the placement, which depends on attack and spins).
+ *
+ * Nodes and children live in arenas, and refer to each other by index. Nodes are updated through
+ * atomics, so selection takes no locks. Advancing slides what is kept to the front of the arenas,
+ * freeing the rest in bulk.
+ * Indices are only stable between advances: hold the tree's read lock across a selection and its
+ * expansion.
+ */
+
 pub struct Tree {
     root_state: RwLock<game::State>,
-    root: Arc<Mutex<Node>>,
+    root: u32,
+    nodes: Arena<Node>,
+    children: Arena<OnceLock<Child>>,
+    /// Nodes by `State::key()`. Rebuilt on advance.
+    table: Mutex<HashMap<u64, u32>>,
+    policy: Arc<dyn Policy>,
 }
 
 impl Default for Tree {
     fn default() -> Self {
+        Self::new(CAPACITY, Arc::new(policy::MinExpansions))
+    }
+}
+
+impl Tree {
+    /// `capacity` is at least 1, for the root.
+    pub fn new (capacity: usize, policy: Arc<dyn Policy>) -> Self {
+        let capacity = capacity.max(1);
+        let nodes = Arena::new(capacity);
+        let root = nodes.alloc(1).expect("tree has no capacity");
         Self {
             root_state: RwLock::new( game::State::default() ),
-            root: Default::default()
+            root,
+            nodes,
+            children: Arena::new(capacity * 2),
+            table: Default::default(),
+            policy,
         }
     }
-}
 
-impl Tree {
+    /// Bytes held by the tree.
+    pub fn memory (&self) -> usize {
+        let table = self.table.lock().capacity() * std::mem::size_of::<(u64, u32)>();
+        self.nodes.memory() + self.children.memory() + table
+    }
+
+    fn node (&self, index: u32) -> &Node {
+        self.nodes.get(index)
+    }
+
+    fn children_of (&self, index: u32) -> impl Iterator<Item = &Child> {
+        let (first, len) = self.node(index).children();
+        (first..first + len).map(|i| self.children.get(i).get().expect("child was not set"))
+    }
+
+    fn eval (&self, child: &Child) -> Evaluation {
+        Evaluation {
+            present: child.present,
+            future: self.node(child.node).future(),
+        }
+    }
+
+    /// Child with the highest eval.
+    fn best (&self, index: u32) -> Option<&Child> {
+        self.children_of(index)
+            .fold(None, |a: Option<(&Child, Evaluation)>, c| {
+                let eval = self.eval(c);
+                match a {
+                    Some(a) if a.1 >= eval => Some(a),
+                    _ => Some((c, eval)),
+                }
+            })
+            .map(|(c, _)| c)
+    }
+
     pub fn select (&self) -> Option<Selection> {
         let mut list = vec![];
-        let (mut mutex_node, mut state) = {
-            let state = self.root_state.read();
-            (self.root.clone(), state.clone())
-        };
+        let mut eval = Evaluation::default();
+        let mut index = self.root;
+        let mut state = self.root_state.read().clone();
 
         loop {
-            let mut node = mutex_node.lock();
-            match node.select() {
-                SelectionResult::Continue(mutex_child)  => {
-                    drop(node);
-                    let child = mutex_child.lock();
-                    state = state.apply_move(child.get_mv());
-                    drop(child);
-                    list.push(mutex_node);
-                    mutex_node = mutex_child;
+            match self.select_child(index) {
+                SelectionResult::Continue(child)  => {
+                    state = state.apply_move(&child.mv);
+                    eval = self.eval(child);
+                    list.push(index);
+                    index = child.node;
                 },
-                SelectionResult::Leaf    => { node.expanding = true; break; }
-                SelectionResult::Deadend => return None, 
+                SelectionResult::Leaf    => break,
+                SelectionResult::Deadend => return None,
             }
         }
-        list.push(mutex_node);
-        Some(Selection::new(list, state))
+        list.push(index);
+        Some(Selection::new(list, state, eval))
     }
 
-    fn print_best (&self) {
-        let (mut mutex_node, mut state) = {
-            let state = self.root_state.read();
-            (self.root.clone(), state.clone())
-        };
+    fn select_child (&self, index: u32) -> SelectionResult<'_> {
+        let node = self.node(index);
+        if node.expanding.load(Ordering::Acquire) || node.exhausted.load(Ordering::Acquire) {
+            return SelectionResult::Deadend
+        }
 
-        loop {
-            let node = mutex_node.lock();
-
-            let best = 
-                if node.children.len() == 0 {
-                    Err(())
-                } else {
-                    Ok(node.children
-                        .iter()
-                        .fold((Arc::new(Mutex::new(Node::default())), f32::MIN), |a, n| {
-                            let eval = &n.lock().eval;
-                            if eval.get() > a.1 { (n.clone(), eval.get()) } 
-                            else { a }
-                        })
-                        .0)
+        if node.children().1 == 0 {
+            // Claim the leaf. Another worker may have claimed, or expanded, it since.
+            if node.expanding.compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire).is_err() {
+                return SelectionResult::Deadend
+            }
+            if node.children().1 != 0 {
+                node.expanding.store(false, Ordering::Release);
+                return SelectionResult::Deadend
+            }
+            return SelectionResult::Leaf
+        }
+
+        let (children, candidates): (Vec<_>, Vec<_>) = self.children_of(index)
+            .filter(|child| {
+                let node = self.node(child.node);
+                !node.expanding.load(Ordering::Acquire) && !node.exhausted.load(Ordering::Acquire)
+            })
+            .map(|child| (child, Candidate {
+                eval: self.eval(child).get(),
+                expansions: self.node(child.node).expansions.load(Ordering::Relaxed),
+            }))
+            .unzip();
+        if candidates.is_empty() {
+            // Keeps policies from descending here again, once there is nothing left below.
+            if self.children_of(index).all(|child| self.node(child.node).exhausted.load(Ordering::Acquire)) {
+                node.exhausted.store(true, Ordering::Release);
+            }
+            return SelectionResult::Deadend
+        }
+
+        let parent = node.expansions.fetch_add(1, Ordering::Relaxed);
+        SelectionResult::Continue(children[self.policy.choose(parent, &candidates)])
+    }
+
+    /// Links generated children under the selected leaf. Children whose state is already in the
+    /// tree share its node. Returns the backprop, and how many children were merged.
+    /// None if the tree is full, in which case the leaf is left unexpanded and selectable, and the
+    /// table untouched. A run of slots handed out before the other failed is linked nowhere, and
+    /// is reclaimed at the next advance.
+    pub fn expand (&self, selection: &Selection, generated: Vec<Generated>) -> Option<(Backprop, u64)> {
+        let len = generated.len() as u32;
+        let leaf = self.node(selection.leaf());
+        let mut merged = 0;
+        {
+            let mut table = self.table.lock();
+
+            // Slots for every new state, before any is entered in the table.
+            let mut new = HashSet::new();
+            for generated in &generated {
+                if !table.contains_key(&generated.key) {
+                    new.insert(generated.key);
+                }
+            }
+            let nodes = if new.is_empty() { Some(0) } else { self.nodes.alloc(new.len()) };
+            let first = nodes.and_then(|_| self.children.alloc(generated.len()));
+            let (Some(mut next), Some(first)) = (nodes, first) else {
+                leaf.expanding.store(false, Ordering::Release);
+                return None;
+            };
+
+            for (i, generated) in generated.into_iter().enumerate() {
+                let node = match table.get(&generated.key) {
+                    Some(&node) => {
+                        merged += 1;
+                        node
+                    },
+                    None => {
+                        let node = next;
+                        next += 1;
+                        table.insert(generated.key, node);
+                        node
+                    },
                 };
 
-            if let Ok(next) = best {
-                drop(node);
-                let child = next.lock();
-                let mut stats = Default::default();
-                (state, stats) = state.apply_move_with_stats(child.get_mv());
-                println!("{:?}\t{:?}\t{:?}", child.mv, child.eval, stats);
-                drop(child);
-
-                mutex_node = next;
-            } else { 
-                break;
+                let child = Child {
+                    mv: generated.mv,
+                    present: generated.present,
+                    node,
+                };
+                let _ = self.children.get(first + i as u32).set(child);
             }
+            leaf.set_children(first, len);
         }
-        println!("best:\n{}", state);
+        leaf.expanding.store(false, Ordering::Release);
+
+        // Merged children may already have been searched, so their future counts too.
+        let backprop = Backprop {
+            score: self.children_of(selection.leaf())
+                .map(|c| self.eval(c))
+                .max_by(|a, b| a.partial_cmp(&b).unwrap())
+                .unwrap()
+                .get()
+        };
+
+        Some((backprop, merged))
     }
 
-    pub fn solution (&self) -> Result<Node, ()> {
-        self.print_best();
+    /// Whether every line from the root was searched as deep as it goes. Nothing is left to select
+    /// until the next advance.
+    pub fn exhausted (&self) -> bool {
+        self.node(self.root).exhausted.load(Ordering::Acquire)
+    }
 
-        // Find child with highest eval.
-        let child = {
-            let root = self.root.lock();
+    /// Marks the selected leaf as having nothing to expand, until the next advance.
+    pub fn exhaust (&self, selection: &Selection) {
+        let leaf = self.node(selection.leaf());
+        leaf.exhausted.store(true, Ordering::Release);
+        leaf.expanding.store(false, Ordering::Release);
+    }
 
-            if root.children.len() == 0 {
-                panic!("Root has no children");
-                //return Err(())
-            }
+    // Applys backpropagation update to nodes selected for the relavent expansion.
+    // Since the list is in decending order, applies it in reverse.
+    // Only the selected path is updated. Other parents of a shared node see the update when they
+    // next compare its evaluation.
+    pub fn backprop (&self, selection: &Selection, backprop: Backprop) {
+        for &index in selection.list.iter().rev() {
+            self.node(index).apply(&backprop);
+        }
+    }
 
-            root.children
-                .iter()
-                .fold(None, |a, n| -> Option<(_, f32)> {
-                    let eval = &n.lock().eval;
-                    match a { 
-                        None => Some((n.clone(), eval.get())),
-                        Some(a) =>
-                            if eval.get() > a.1 { Some((n.clone(), eval.get())) } 
-                            else { Some(a) }
-                    }
-                })
-                .expect("No children in root, could not find one with highest eval.")
-                .0.lock()
-                .clone()
+    /// Follows the best child from the root down to a leaf.
+    pub fn principal_variation (&self) -> PrincipalVariation {
+        let mut index = self.root;
+        let mut state = self.root_state.read().clone();
+        let mut line = vec![];
+
+        while let Some(child) = self.best(index) {
+            let stats;
+            (state, stats) = state.apply_move_with_stats(&child.mv);
+            line.push(Planned {
+                mv: child.mv.clone(),
+                eval: self.eval(child).get(),
+                stats,
+            });
+
+            index = child.node;
+        }
+        PrincipalVariation { line, state }
+    }
+
+    pub fn solution (&self) -> Result<Child, QuaternionError> {
+        // Find child with highest eval.
+        let Some(child) = self.best(self.root) else {
+            let state = self.root_state.read();
+            return Err(
+                if state.queue_len() == 0 { QuaternionError::EmptyQueue }
+                else if state.legal_moves().is_empty() { QuaternionError::ToppedOut }
+                else { QuaternionError::NoSolution }
+            )
         };
+        let child = child.clone();
 
 
         // Debug: print state.
@@ -123,48 +291,156 @@ impl Tree {
         Ok(child)
     }
 
-    
+
     pub fn advance (&mut self, state: &game::State) {
         // Find child with matching state.
-        let mut root_state = self.root_state.write();
-        let mut root = self.root.lock();       
+        let root_state = self.root_state.get_mut();
+
+        // Same position with more previews revealed: keep the whole tree.
+        if state.extends(root_state) {
+            *root_state = state.clone();
+            self.revive();
+            self.rehash();
+            return;
+        }
+        let root_state = root_state.clone();
 
         let child = {
 
             let mut out = None;
-            for child in &root.children {
+            for child in self.children_of(self.root) {
                 let child_state = root_state.clone()
-                    .apply_move(&child.lock().mv);
+                    .apply_move(&child.mv);
 
                 // NOTE: IMPORTANT: This line was changed from before the refactoring. Used to be a
                 // function called 'is_child_of(a, b)' that seemed to just check for equality between
                 // two states.
-                if state.eq(&child_state) {
-                    out = Some(child.clone());
+                // Previews revealed since are appended onto the child's queue, hence `extends`.
+                if state.extends(&child_state) {
+                    out = Some(child.node);
                 }
             }
             out
         };
-        
-        *root_state = state.clone();
+
+        *self.root_state.get_mut() = state.clone();
 
         // If is a child.
-        if let Some(child) = child 
-        { // Reassign root & state.
-            drop(root);
-            self.root = child.clone();
-            self.root.lock().expanding = false;
-        } else 
+        if let Some(child) = child
+        { // Reassign root, and free the rest of the old tree.
+            self.compact(child);
+            self.revive();
+            self.rehash();
+        } else
         { // Else, reset tree
-            *root = Default::default();
+            self.nodes.clear();
+            self.children.clear();
+            self.table.get_mut().clear();
+            self.root = self.nodes.alloc(1).expect("tree has no capacity");
+        }
+    }
+
+    /// Lets every node be selected again. Workers are not expanding while the tree is advanced,
+    /// and previews revealed since let exhausted leaves be searched deeper.
+    fn revive (&mut self) {
+        for i in 0..self.nodes.len() as u32 {
+            let node = self.nodes.get_mut(i);
+            *node.expanding.get_mut() = false;
+            *node.exhausted.get_mut() = false;
+        }
+    }
+
+    /// Makes `root` the root, keeping only what is reachable from it. What is kept is slid to the
+    /// front of the arenas.
+    fn compact (&mut self, root: u32) {
+        let mut live_nodes = vec![false; self.nodes.len()];
+        let mut live_children = vec![false; self.children.len()];
+
+        // Mark
+        let mut stack = vec![root];
+        live_nodes[root as usize] = true;
+        while let Some(index) = stack.pop() {
+            let (first, len) = self.node(index).children();
+            for i in first..first + len {
+                live_children[i as usize] = true;
+            }
+            for child in self.children_of(index) {
+                if !live_nodes[child.node as usize] {
+                    live_nodes[child.node as usize] = true;
+                    stack.push(child.node);
+                }
+            }
+        }
+
+        // Point to where things will be, then slide them there.
+        let node_fwd = arena::forward(&live_nodes);
+        let child_fwd = arena::forward(&live_children);
+        for (i, _) in live_nodes.iter().enumerate().filter(|(_, live)| **live) {
+            let node = self.nodes.get(i as u32);
+            let (first, len) = node.children();
+            if len > 0 {
+                node.set_children(child_fwd[first as usize], len);
+            }
+        }
+        for (i, _) in live_children.iter().enumerate().filter(|(_, live)| **live) {
+            let child = self.children.get_mut(i as u32).get_mut().expect("child was not set");
+            child.node = node_fwd[child.node as usize];
         }
+        self.nodes.compact(&live_nodes);
+        self.children.compact(&live_children);
+        self.root = node_fwd[root as usize];
+    }
+
+    /// Enters every node below the root in the table afresh. Keys cover the whole queue, so the
+    /// previews revealed on advance change the key of every state kept.
+    fn rehash (&mut self) {
+        let mut table = HashMap::with_capacity(self.nodes.len());
+        let mut stack = vec![(self.root, self.root_state.get_mut().clone())];
+        while let Some((index, state)) = stack.pop() {
+            for child in self.children_of(index) {
+                let state = state.clone().apply_move(&child.mv);
+                // Reached through another parent already.
+                if let Entry::Vacant(entry) = table.entry(state.key()) {
+                    entry.insert(child.node);
+                    stack.push((child.node, state));
+                }
+            }
+        }
+        *self.table.get_mut() = table;
     }
 }
 
 
 
-enum SelectionResult {
-    Continue (Arc<Mutex<Node>>),
+/// The line the bot plans, best placement first.
+#[derive(Clone)]
+pub struct PrincipalVariation {
+    pub line: Vec<Planned>,
+    /// State after the whole line.
+    pub state: game::State,
+}
+
+/// A placement in the principal variation.
+#[derive(Clone, Debug)]
+pub struct Planned {
+    pub mv: game::Move,
+    /// Evaluation of the placement, blended with the best found below it.
+    pub eval: f32,
+    pub stats: game::MoveStats,
+}
+
+impl std::fmt::Display for PrincipalVariation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for planned in &self.line {
+            writeln!(f, "{:?}\t{:.2}\t{:?}", planned.mv, planned.eval, planned.stats)?;
+        }
+        write!(f, "best:\n{}", self.state)
+    }
+}
+
+
+enum SelectionResult<'a> {
+    Continue (&'a Child),
     Deadend,
     Leaf,
 }
@@ -176,20 +452,6 @@ pub struct Evaluation {
     future: Option<f32>
 }
 impl Evaluation {
-    pub fn new (score: f32) -> Self {
-        Evaluation {
-            present: score, 
-            future: None 
-        }
-    }
-    pub fn apply(&mut self, backprop: &Backprop) {
-        if let Some(future) = &mut self.future {
-            *future = (*future).max(backprop.score);
-        } else {
-            self.future = Some(backprop.score);
-        }
-    }
-
     const INHERITANCE_F: f32 = 0.3;
     fn get(&self) -> f32 {
         if let Some(future) = self.future {
@@ -211,70 +473,96 @@ impl PartialOrd for Evaluation {
 }
 
 
-#[derive(Debug, Clone, Default)]
+/// A state in the tree. Shared by every parent that reaches the state.
 pub struct Node {
-    pub eval: Evaluation,
-    pub mv: game::Move,
-    pub children: Vec<Arc<Mutex<Node>>>,
-    pub expanding: bool,
-    pub expansions: u32
+    /// Bits of the best evaluation backpropagated from below. `NO_FUTURE` until then.
+    future: AtomicU32,
+    /// Range of the node's children in the arena, as `first << 32 | len`. Set when expanded.
+    children: AtomicU64,
+    expanding: AtomicBool,
+    /// Nothing left to expand below, as the leaves are past the previews or topped out.
+    exhausted: AtomicBool,
+    expansions: AtomicU32,
 }
 
-impl Node {
-    fn select (&mut self) -> SelectionResult {
-        if self.expanding {
-            SelectionResult::Deadend
-        } else 
-        if self.children.is_empty() {
-            SelectionResult::Leaf
-        } else {
-            let candidates: Vec<_> = self.children.iter().filter(|child| !child.lock().expanding).collect();
-            if candidates.is_empty() { return SelectionResult::Deadend }
-
-            self.expansions += 1;
+/// A NaN, which no evaluation is.
+const NO_FUTURE: u32 = u32::MAX;
 
-            let out = candidates.iter().min_by_key(|c| c.lock().expansions).unwrap();
-            SelectionResult::Continue((*out).clone())
-            
+impl Default for Node {
+    fn default () -> Self {
+        Self {
+            future: AtomicU32::new(NO_FUTURE),
+            children: AtomicU64::new(0),
+            expanding: AtomicBool::new(false),
+            exhausted: AtomicBool::new(false),
+            expansions: AtomicU32::new(0),
         }
     }
+}
 
-    pub fn expand (&mut self, children: Vec<Arc<Mutex<Node>>>) -> Backprop {
-        let backprop = Backprop {
-            score: children
-                .iter()
-                .map(|c| c.lock().eval.clone())
-                .max_by(|a, b| a.partial_cmp(&b).unwrap())
-                .unwrap()
-                .get()
-        };
+impl Node {
+    fn future (&self) -> Option<f32> {
+        match self.future.load(Ordering::Acquire) {
+            NO_FUTURE => None,
+            bits => Some(f32::from_bits(bits)),
+        }
+    }
 
-        self.children = children;
-        self.expanding = false;
+    fn children (&self) -> (u32, u32) {
+        let range = self.children.load(Ordering::Acquire);
+        ((range >> 32) as u32, range as u32)
+    }
 
-        backprop
+    fn set_children (&self, first: u32, len: u32) {
+        self.children.store((first as u64) << 32 | len as u64, Ordering::Release);
     }
 
-    pub fn get_mv (&self) -> &game::Move {
-        &self.mv
+    fn apply (&self, backprop: &Backprop) {
+        let _ = self.future.fetch_update(Ordering::AcqRel, Ordering::Acquire, |bits| {
+            let future = match bits {
+                NO_FUTURE => backprop.score,
+                bits => f32::from_bits(bits).max(backprop.score),
+            };
+            Some(future.to_bits())
+        });
     }
 }
 
-pub fn gen_children (state: &game::State) -> Vec<Node> {
+/// A move from a node into a child node.
+#[derive(Clone)]
+pub struct Child {
+    pub mv: game::Move,
+    /// Evaluation of the placement alone.
+    pub present: f32,
+    /// Index of the node moved into.
+    pub node: u32,
+}
+
+/// A child generated from a state, not yet linked into the tree.
+pub struct Generated {
+    mv: game::Move,
+    present: f32,
+    key: u64,
+}
+
+pub fn gen_children (state: &game::State, config: &game::eval::EvalConfig) -> Vec<Generated> {
     game::movegen::gen_moves(state)
         .into_iter()
-        .map(|mov| state.clone().make_node(mov, game::eval::Mode::Norm))
+        .map(|mov| {
+            let (child, present) = state.clone().make_child(&mov, game::eval::Mode::Norm, config);
+            Generated { mv: mov, present, key: child.key() }
+        })
         .collect()
 }
 
 // Prune / Apply Cutoff
-pub fn prune_children (mut nodes: Vec<Node>, selection: &Selection) -> Vec<Node> {
-    nodes.sort_by(|a, b| b.eval.partial_cmp(&a.eval).unwrap());
+pub fn prune_children (mut nodes: Vec<Generated>, selection: &Selection) -> Vec<Generated> {
+    nodes.sort_by(|a, b| b.present.partial_cmp(&a.present).unwrap());
     let n = nodes.len();
-    let cutoff_index = { 
-        let parent_eval = selection.get_leaf().eval;
+    let cutoff_index = {
+        let parent_eval = selection.get_eval();
         let mut i = 0;
-        while i < n && nodes[i].eval.present > CUTOFF_F * parent_eval.get() { i += 1; }
+        while i < n && nodes[i].present > CUTOFF_F * parent_eval.get() { i += 1; }
         i.max(20)
     };
     nodes
@@ -284,13 +572,16 @@ pub fn prune_children (mut nodes: Vec<Node>, selection: &Selection) -> Vec<Node>
 }
 
 pub struct Selection {
-    list: Vec<Arc<Mutex<Node>>>,
-    state: game::State, 
+    /// Indices of the nodes selected, from the root down to the leaf.
+    list: Vec<u32>,
+    state: game::State,
+    /// Eval of the move into the leaf. Default at the root.
+    eval: Evaluation,
 }
 impl Selection {
-    pub fn new (list: Vec<Arc<Mutex<Node>>>, state: game::State) -> Self {
+    pub fn new (list: Vec<u32>, state: game::State, eval: Evaluation) -> Self {
         Self {
-            list, state 
+            list, state, eval
         }
     }
 
@@ -298,28 +589,12 @@ impl Selection {
         &self.state
     }
 
-    pub fn get_leaf(&self) -> Node {
-        self.list
-            .last()
-            .unwrap()
-            .lock()
-            .clone()
+    pub fn get_eval(&self) -> &Evaluation {
+        &self.eval
     }
 
-    pub fn expand(&self, children: Vec<Arc<Mutex<Node>>>) -> Backprop {
-        self.list
-            .last()
-            .unwrap()
-            .lock()
-            .expand(children)
-    }
-
-    // Applys backpropagation update to nodes selected for the relavent expansion.
-    // Since self.list is in decending order, applies it in reverse. 
-    pub fn backprop(&self, backprop: Backprop) {
-        for node in self.list.iter().rev() {
-            node.lock().eval.apply(&backprop);
-        }
+    fn leaf (&self) -> u32 {
+        *self.list.last().unwrap()
     }
 }
 
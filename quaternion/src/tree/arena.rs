@@ -0,0 +1,98 @@
+#[cfg(test)]
+mod tests;
+
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Slots per chunk. Chunks are allocated as the arena grows, and kept until it is dropped.
+const CHUNK: usize = 1 << 14;
+
+/// Fixed capacity store indexed by `u32`. Slots are handed out concurrently, in contiguous runs,
+/// and are only freed in bulk, by `compact(..)` or `clear()`.
+pub struct Arena<T> {
+    chunks: Box<[OnceLock<Box<[T]>>]>,
+    /// Slots handed out. May pass the capacity once full.
+    len: AtomicUsize,
+}
+
+impl<T: Default> Arena<T> {
+    pub fn new (capacity: usize) -> Self {
+        Self {
+            chunks: (0..capacity.div_ceil(CHUNK)).map(|_| OnceLock::new()).collect(),
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn capacity (&self) -> usize {
+        self.chunks.len() * CHUNK
+    }
+
+    pub fn len (&self) -> usize {
+        self.len.load(Ordering::Acquire).min(self.capacity())
+    }
+
+    /// Bytes held by the chunks allocated so far.
+    pub fn memory (&self) -> usize {
+        self.chunks.iter().filter(|chunk| chunk.get().is_some()).count() * CHUNK * std::mem::size_of::<T>()
+    }
+
+    /// Hands out `n` contiguous slots, returning the index of the first. None if the arena is full.
+    pub fn alloc (&self, n: usize) -> Option<u32> {
+        let start = self.len.fetch_add(n, Ordering::AcqRel);
+        if start + n > self.capacity() {
+            return None;
+        }
+        for chunk in &self.chunks[start / CHUNK .. (start + n).div_ceil(CHUNK)] {
+            chunk.get_or_init(|| (0..CHUNK).map(|_| T::default()).collect());
+        }
+        Some(start as u32)
+    }
+
+    pub fn get (&self, index: u32) -> &T {
+        let index = index as usize;
+        &self.chunks[index / CHUNK].get().expect("slot was not allocated")[index % CHUNK]
+    }
+
+    pub fn get_mut (&mut self, index: u32) -> &mut T {
+        self.slot_mut(index as usize).expect("slot was not allocated")
+    }
+
+    fn slot_mut (&mut self, index: usize) -> Option<&mut T> {
+        self.chunks[index / CHUNK]
+            .get_mut()
+            .map(|chunk| &mut chunk[index % CHUNK])
+    }
+
+    /// Slides the `live` slots down to the front, keeping their order, and resets the rest.
+    /// Slot `i` moves to `forward(live)[i]`.
+    pub fn compact (&mut self, live: &[bool]) {
+        let mut n = 0;
+        for (i, live) in live.iter().enumerate() {
+            let Some(slot) = self.slot_mut(i) else { continue };
+            let value = std::mem::take(slot);
+            if *live {
+                *self.slot_mut(n).unwrap() = value;
+                n += 1;
+            }
+        }
+        *self.len.get_mut() = n;
+    }
+
+    /// Resets every slot handed out.
+    pub fn clear (&mut self) {
+        self.compact(&vec![false; self.len()]);
+    }
+}
+
+/// Where each slot moves to in `Arena::compact(live)`. Dead slots map to `u32::MAX`.
+pub fn forward (live: &[bool]) -> Vec<u32> {
+    let mut n = 0;
+    live.iter()
+        .map(|live| if *live {
+            n += 1;
+            n - 1
+        } else {
+            u32::MAX
+        })
+        .collect()
+}
@@ -0,0 +1,28 @@
+use super::*;
+
+
+#[test]
+fn compact () {
+    let mut arena: Arena<u32> = Arena::new(CHUNK * 2);
+    let first = arena.alloc(CHUNK + 3).unwrap();
+    for i in 0..CHUNK as u32 + 3 {
+        *arena.get_mut(first + i) = i + 1;
+    }
+    assert_eq!(arena.memory(), 2 * CHUNK * 4);
+
+    // Keeps order across chunks, and resets what is dropped
+    let live: Vec<_> = (0..CHUNK + 3).map(|i| i % 3 == 0 || i == CHUNK + 1).collect();
+    let fwd = forward(&live);
+    arena.compact(&live);
+    assert_eq!(arena.len(), live.iter().filter(|l| **l).count());
+    assert_eq!(*arena.get(fwd[CHUNK + 1]), CHUNK as u32 + 2);
+    assert_eq!(*arena.get(fwd[CHUNK + 2]), CHUNK as u32 + 3);
+    assert_eq!(*arena.get(arena.len() as u32), 0);
+    assert_eq!(fwd[1], u32::MAX);
+
+    // Bounded by capacity
+    assert!(arena.alloc(CHUNK * 2).is_none());
+    assert!(arena.alloc(1).is_none());
+    arena.clear();
+    assert_eq!(arena.alloc(1), Some(0));
+}
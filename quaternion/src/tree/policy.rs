@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod tests;
+
+use std::cell::Cell;
+use std::hash::{BuildHasher, Hasher};
+
+/*
+ * Selection policies: which child a selection descends into.
+ * Children being expanded by another worker are not offered.
+ */
+
+/// What a policy sees of a child.
+#[derive(Clone, Copy, Debug)]
+pub struct Candidate {
+    /// The child's evaluation, blended with the best found below it.
+    pub eval: f32,
+    /// Selections that went through the child.
+    pub expansions: u32,
+}
+
+pub trait Policy: Send + Sync {
+    /// Index of the candidate to descend into. `parent` is the parent's expansions.
+    /// `candidates` is never empty.
+    fn choose (&self, parent: u32, candidates: &[Candidate]) -> usize;
+}
+
+
+/// The child selected the least. Ignores evaluations, so the tree grows breadth-first.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MinExpansions;
+
+impl Policy for MinExpansions {
+    fn choose (&self, _parent: u32, candidates: &[Candidate]) -> usize {
+        (0..candidates.len())
+            .min_by_key(|i| candidates[*i].expansions)
+            .unwrap()
+    }
+}
+
+
+/// UCT. Evaluations are normalized to [0, 1] among siblings, since their scale is arbitrary.
+#[derive(Clone, Copy, Debug)]
+pub struct Uct {
+    /// Weight of exploration against the normalized evaluation.
+    pub exploration: f32,
+}
+
+impl Default for Uct {
+    fn default () -> Self {
+        Self { exploration: 0.5 }
+    }
+}
+
+impl Policy for Uct {
+    fn choose (&self, parent: u32, candidates: &[Candidate]) -> usize {
+        let (min, max) = candidates
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(min, max), c| (min.min(c.eval), max.max(c.eval)));
+        let range = (max - min).max(f32::EPSILON);
+        let ln_parent = (parent as f32 + 1.0).ln();
+
+        let score = |c: &Candidate| {
+            let q = (c.eval - min) / range;
+            q + self.exploration * (ln_parent / (c.expansions as f32 + 1.0)).sqrt()
+        };
+        (0..candidates.len())
+            .max_by(|a, b| score(&candidates[*a]).total_cmp(&score(&candidates[*b])))
+            .unwrap()
+    }
+}
+
+
+/// Random, weighted by rank, as in Cold Clear. Rank `r` of `n` is picked when `u^bias * n` falls
+/// on it, `u` being uniform in [0, 1). The higher the bias, the more often the best is picked.
+#[derive(Clone, Copy, Debug)]
+pub struct Rank {
+    pub bias: f32,
+}
+
+impl Default for Rank {
+    fn default () -> Self {
+        Self { bias: 2.0 }
+    }
+}
+
+impl Rank {
+    /// The candidate picked when the draw is `u`.
+    fn pick (&self, u: f32, candidates: &[Candidate]) -> usize {
+        let mut ranked: Vec<usize> = (0..candidates.len()).collect();
+        ranked.sort_by(|a, b| candidates[*b].eval.total_cmp(&candidates[*a].eval));
+
+        let rank = (u.powf(self.bias) * candidates.len() as f32) as usize;
+        ranked[rank.min(candidates.len() - 1)]
+    }
+}
+
+impl Policy for Rank {
+    fn choose (&self, _parent: u32, candidates: &[Candidate]) -> usize {
+        self.pick(random(), candidates)
+    }
+}
+
+
+thread_local! {
+    static RNG: Cell<u64> = Cell::new(
+        std::collections::hash_map::RandomState::new().build_hasher().finish() | 1
+    );
+}
+
+/// Uniform in [0, 1). Xorshift, per thread, as selection needs speed more than quality.
+fn random () -> f32 {
+    RNG.with(|rng| {
+        let mut x = rng.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        rng.set(x);
+        (x >> 40) as f32 / (1u64 << 24) as f32
+    })
+}
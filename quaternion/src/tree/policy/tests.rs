@@ -0,0 +1,46 @@
+use super::*;
+
+
+fn candidates (list: &[(f32, u32)]) -> Vec<Candidate> {
+    list.iter()
+        .map(|&(eval, expansions)| Candidate { eval, expansions })
+        .collect()
+}
+
+#[test]
+fn min_expansions () {
+    let candidates = candidates(&[(10.0, 3), (-5.0, 1), (20.0, 2)]);
+    assert_eq!(MinExpansions.choose(6, &candidates), 1);
+}
+
+#[test]
+fn uct () {
+    // Equally explored: the best
+    let even = candidates(&[(10.0, 3), (-5.0, 3), (20.0, 3)]);
+    assert_eq!(Uct::default().choose(9, &even), 2);
+
+    // Unexplored children are tried before the best is refined
+    let unexplored = candidates(&[(10.0, 0), (-5.0, 30), (20.0, 30)]);
+    assert_eq!(Uct::default().choose(60, &unexplored), 0);
+
+    // Without exploration, only evaluations matter
+    assert_eq!(Uct { exploration: 0.0 }.choose(60, &unexplored), 2);
+}
+
+#[test]
+fn rank () {
+    // Ordered by eval: 2, 0, 3, 1
+    let candidates = candidates(&[(10.0, 0), (-5.0, 0), (20.0, 0), (0.0, 0)]);
+    let picks = |rank: Rank, draws: &[f32]| -> Vec<usize> {
+        draws.iter().map(|&u| rank.pick(u, &candidates)).collect()
+    };
+
+    // Bias 2: rank `u^2 * 4`
+    assert_eq!(picks(Rank::default(), &[0.0, 0.49, 0.5, 0.7, 0.75, 0.86, 0.87, 0.999]), [2, 2, 0, 0, 3, 3, 1, 1]);
+    // Bias 1: uniform
+    assert_eq!(picks(Rank { bias: 1.0 }, &[0.1, 0.3, 0.6, 0.8]), [2, 0, 3, 1]);
+
+    for _ in 0..1000 {
+        assert!((0.0..1.0).contains(&random()));
+    }
+}
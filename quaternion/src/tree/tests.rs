@@ -0,0 +1,174 @@
+use super::*;
+use crate::worker::Worker;
+use game::{Key, Piece, State};
+
+
+#[test]
+fn principal_variation () {
+    let queue = [Piece::T, Piece::I, Piece::O, Piece::L, Piece::J, Piece::S, Piece::Z];
+    let state = State::new(&[], queue, None, 0, 0);
+    let worker = Worker::new();
+    worker.advance(&state).unwrap();
+    worker.start(&mut worker.state.lock()).unwrap();
+    for _ in 0..200 {
+        worker.work();
+    }
+
+    // Follows the best move, and replays to the state given
+    let pv = worker.principal_variation();
+    assert!(pv.line.len() > 1);
+    assert_eq!(pv.line[0].mv.parse_list(), worker.solution().unwrap().mv.parse_list());
+
+    let end = pv.line
+        .iter()
+        .fold(state, |state, planned| state.apply_move(&planned.mv));
+    assert!(end == pv.state);
+}
+
+#[test]
+fn errors () {
+    let queue = [Piece::T, Piece::I, Piece::O, Piece::L];
+    let worker = Worker::new();
+    assert_eq!(worker.advance(&State::new(&[], [], None, 0, 0)), Err(QuaternionError::EmptyQueue));
+
+    // Not searched yet
+    worker.advance(&State::new(&[], queue, None, 0, 0)).unwrap();
+    assert_eq!(worker.solution().err(), Some(QuaternionError::NoSolution));
+
+    // Topped out
+    worker.advance(&State::new(&[[true; 10]; 40], queue, None, 0, 0)).unwrap();
+    assert_eq!(worker.solution().err(), Some(QuaternionError::ToppedOut));
+
+    let state = &mut worker.state.lock();
+    assert_eq!(worker.stop(state), Err(QuaternionError::Stopped));
+    worker.start(state).unwrap();
+    assert_eq!(worker.start(state), Err(QuaternionError::Running));
+}
+
+#[test]
+fn think_nodes () {
+    let queue = [Piece::T, Piece::I, Piece::O, Piece::L, Piece::J, Piece::S, Piece::Z];
+    let state = State::new(&[], queue, None, 0, 0);
+    for bot in [crate::Quaternion::single(), crate::Quaternion::with_threads(2)] {
+        bot.advance(&state).unwrap();
+        assert!(bot.think_nodes(5000).is_ok());
+        assert!(bot.stats().nodes >= 5000);
+
+        // Budgets past a million nodes are spent in full. Counted from a million, to keep it short.
+        bot.advance(&state).unwrap();
+        bot.worker.state.lock().stats.nodes = 1_000_000;
+        assert!(bot.think_nodes(1_005_000).is_ok());
+        assert!(bot.stats().nodes >= 1_005_000);
+
+        // Returns once there is nothing left to search, short of the budget.
+        let short = State::new(&[], [Piece::T, Piece::I, Piece::O], None, 0, 0);
+        bot.advance(&short).unwrap();
+        assert!(bot.think_nodes(u64::MAX).is_ok());
+        bot.stop().unwrap();
+    }
+}
+
+#[test]
+fn think_for_returns_early () {
+    let short = State::new(&[], [Piece::T, Piece::I, Piece::O], None, 0, 0);
+    for bot in [crate::Quaternion::single(), crate::Quaternion::with_threads(2)] {
+        bot.advance(&short).unwrap();
+
+        // Nothing left to search long before the time is up.
+        let start = std::time::Instant::now();
+        assert!(bot.think_for(std::time::Duration::from_secs(30)).is_ok());
+        assert!(start.elapsed().as_secs() < 10);
+        bot.stop().unwrap();
+    }
+}
+
+#[test]
+fn think_for_runs_to_deadline () {
+    // Deep enough that the search neither exhausts nor fills the tree in the time given.
+    let queue = [Piece::T, Piece::I, Piece::O, Piece::L, Piece::J, Piece::S, Piece::Z];
+    let state = State::new(&[], queue.repeat(2), None, 0, 0);
+    for bot in [crate::Quaternion::single(), crate::Quaternion::with_threads(2)] {
+        bot.advance(&state).unwrap();
+        // As if a long search had already run: the node count alone does not stop it.
+        bot.worker.state.lock().stats.nodes = 1 << 30;
+
+        let duration = std::time::Duration::from_secs(1);
+        let start = std::time::Instant::now();
+        assert!(bot.think_for(duration).is_ok());
+        assert!(start.elapsed() >= duration);
+        bot.stop().unwrap();
+    }
+}
+
+#[test]
+fn expand_when_full () {
+    let queue = [Piece::T, Piece::I, Piece::O, Piece::L, Piece::J, Piece::S, Piece::Z];
+    let mut tree = Tree::new(1, Arc::new(policy::MinExpansions));
+    tree.advance(&State::new(&[], queue, None, 0, 0));
+    let config = Default::default();
+
+    loop {
+        let selection = tree.select().expect("ran out of leaves before the tree filled up");
+        let generated = gen_children(selection.get_state(), &config);
+        let entries = tree.table.lock().len();
+        if tree.expand(&selection, generated).is_some() {
+            continue;
+        }
+
+        // The leaf is left as it was, and the table holds no unlinked nodes.
+        let leaf = tree.node(selection.leaf());
+        assert!(!leaf.expanding.load(Ordering::Acquire));
+        assert_eq!(leaf.children().1, 0);
+        assert_eq!(tree.table.lock().len(), entries);
+        break;
+    }
+
+    // No capacity still holds the root.
+    let bot = crate::Quaternion::builder().threads(0).capacity(0).build();
+    bot.advance(&State::new(&[], queue, None, 0, 0)).unwrap();
+    assert!(bot.think_nodes(1000).is_ok());
+}
+
+#[test]
+fn transpositions_across_advance () {
+    // Placing T then I from hold, or I from hold then T, ends in the same state.
+    let state = State::new(&[], [Piece::T, Piece::O, Piece::L], Some(Piece::I), 0, 0);
+    let mut tree = Tree::default();
+    tree.advance(&state);
+    let config = Default::default();
+    let expand = |tree: &Tree, list: Vec<u32>, state: &State| {
+        let selection = Selection::new(list, state.clone(), Default::default());
+        tree.expand(&selection, gen_children(state, &config)).unwrap().1
+    };
+    let holds = |mv: &game::Move| mv.parse_list().unwrap().contains(&Key::Hold);
+
+    expand(&tree, vec![tree.root], &state);
+    let placed_t = tree.children_of(tree.root).find(|c| !holds(&c.mv)).unwrap().clone();
+    expand(&tree, vec![tree.root, placed_t.node], &state.clone().apply_move(&placed_t.mv));
+
+    // A preview is revealed. What was kept merges with what is expanded after.
+    let state = State::new(&[], [Piece::T, Piece::O, Piece::L, Piece::J], Some(Piece::I), 0, 0);
+    tree.advance(&state);
+    let placed_i: Vec<_> = tree.children_of(tree.root).filter(|c| holds(&c.mv)).cloned().collect();
+    let merged: u64 = placed_i.iter()
+        .map(|c| expand(&tree, vec![tree.root, c.node], &state.clone().apply_move(&c.mv)))
+        .sum();
+    assert!(merged > 0);
+
+    // Equal states reached in either order share one node.
+    let placed_t = tree.children_of(tree.root).find(|c| !holds(&c.mv)).unwrap().clone();
+    let after_t = state.clone().apply_move(&placed_t.mv);
+    let mut shared = 0;
+    for a in tree.children_of(placed_t.node) {
+        let a_state = after_t.clone().apply_move(&a.mv);
+        for c in &placed_i {
+            for b in tree.children_of(c.node) {
+                if state.clone().apply_move(&c.mv).apply_move(&b.mv) == a_state {
+                    assert_eq!(a.node, b.node);
+                    shared += 1;
+                }
+            }
+        }
+    }
+    assert!(shared > 0);
+}
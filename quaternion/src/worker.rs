@@ -2,133 +2,197 @@ use std::sync::Arc;
 
 use parking_lot::{RwLock, Mutex, Condvar};
 use super::tree::*;
+use super::tree::policy::{Policy, MinExpansions};
 use super::game;
+use super::clock;
+use super::QuaternionError;
 
 
 pub struct Worker {
     tree: RwLock<Tree>,
     pub state: Mutex<State>,
-    pub blocker: Condvar
+    pub blocker: Condvar,
+    /// Signalled by workers going idle, for want of work.
+    pub idle: Condvar,
+    /// Evaluation parameters used for every node this worker creates.
+    config: game::eval::EvalConfig,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct State {
-    pub node_limit: u64,
+    /// Set when the tree runs out of capacity. Cleared on advance, which frees what was pruned.
+    pub full: bool,
+    /// Set when the tree has been searched as deep as it goes. Cleared on advance.
+    pub exhausted: bool,
+    /// Time (see `clock::now()`) past which workers idle. Cleared on advance.
+    pub deadline: Option<f64>,
+    /// Nodes past which workers idle. Cleared on advance.
+    pub node_budget: Option<u64>,
     pub run: bool,
     pub stats: BotStats,
 }
 
 #[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BotStats {
-    pub nodes: u64
-}
-
-impl Default for State {
-    fn default() -> Self {
-        Self {
-            stats: Default::default(),
-            node_limit: 1000000,
-            run: false
-        }
-    }
+    pub nodes: u64,
+    /// Generated nodes whose state was already in the tree, and were merged into it.
+    pub merged: u64,
+    /// Bytes held by the tree.
+    pub memory: usize,
 }
 
 impl State {
-    fn should_work(&self) -> bool {
-        self.run && self.stats.nodes < self.node_limit 
+    pub fn should_work(&self) -> bool {
+        self.run && 
+        !self.full &&
+        !self.exhausted &&
+        self.node_budget.is_none_or(|budget| self.stats.nodes < budget) &&
+        self.deadline.is_none_or(|deadline| clock::now() < deadline)
     }
 }
 
 impl Worker {
     pub fn new () -> Self {
+        Self::with_config(Default::default(), CAPACITY, Arc::new(MinExpansions))
+    }
+
+    /// `capacity` is the most nodes the tree holds. `policy` selects the nodes to expand.
+    pub fn with_config (config: game::eval::EvalConfig, capacity: usize, policy: Arc<dyn Policy>) -> Self {
         Self {
-            tree: Default::default(),
+            tree: RwLock::new(Tree::new(capacity, policy)),
             state: Default::default(),
-            blocker: Condvar::new()
+            blocker: Condvar::new(),
+            idle: Condvar::new(),
+            config,
         }
     }
 
-    /// Starts worker cycle. Panics if is already running
-    pub fn start (&self, state: &mut State) {
-        assert!(state.run == false);
+    /// Starts worker cycle. Errs if is already running
+    pub fn start (&self, state: &mut State) -> Result<(), QuaternionError> {
+        if state.run {
+            return Err(QuaternionError::Running);
+        }
         state.run = true;
         self.blocker.notify_all();
+        Ok(())
     }
 
-    /// Starts worker cycle. Panics if is not running 
-    pub fn stop (&self, state: &mut State) {
-        assert!(state.run == true);
+    /// Stops worker cycle. Errs if is not running
+    pub fn stop (&self, state: &mut State) -> Result<(), QuaternionError> {
+        if !state.run {
+            return Err(QuaternionError::Stopped);
+        }
         state.run = false;
         self.blocker.notify_all();
+        Ok(())
+    }
+
+    /// Sets time past which workers idle. Wakes workers, as they may be idling on an old deadline.
+    pub fn set_deadline (&self, state: &mut State, deadline: Option<f64>) {
+        state.deadline = deadline;
+        self.blocker.notify_all();
+    }
+
+    /// Sets nodes past which workers idle. Wakes workers, as they may be idling on an old budget.
+    pub fn set_node_budget (&self, state: &mut State, budget: Option<u64>) {
+        state.node_budget = budget;
+        self.blocker.notify_all();
     }
 
-    pub fn solution (&self) -> Result<Node, ()> {
+    pub fn solution (&self) -> Result<Child, QuaternionError> {
         self.tree.read().solution()
     }
 
+    pub fn principal_variation (&self) -> PrincipalVariation {
+        self.tree.read().principal_variation()
+    }
+
+    pub fn stats (&self) -> BotStats {
+        let mut stats = self.state.lock().stats.clone();
+        stats.memory = self.tree.read().memory();
+        stats
+    }
+
     /// Advance worker into new state.
     /// Does not affect running/stopping state of the bot.
-    pub fn advance (&self, state: &game::State) {
+    /// Errs on a state without pieces, which can not be searched.
+    pub fn advance (&self, state: &game::State) -> Result<(), QuaternionError> {
+        if state.queue_len() == 0 {
+            return Err(QuaternionError::EmptyQueue);
+        }
+
         // If is running, stop.
         let was_running = {
             let state = &mut self.state.lock();
-            if state.run {
-                self.stop(state);
-                true
-            } else { false }
+            self.stop(state).is_ok()
         };
 
         self.tree.write().advance(state);
 
-        // Reset Stats
-        self.state.lock().stats = Default::default();
+        // Reset Stats & deadline
+        {
+            let state = &mut self.state.lock();
+            state.stats = Default::default();
+            state.deadline = None;
+            state.node_budget = None;
+            state.full = false;
+            state.exhausted = false;
+        }
 
         // If was running, continue.
         if was_running {
-            self.start(&mut self.state.lock());
+            self.start(&mut self.state.lock())?;
         }
+        Ok(())
     }
 
     pub fn work (&self) {
+        // Held throughout, as advancing moves nodes around.
+        let tree = self.tree.read();
         let selection = 
-            if let Some (out) = self.tree.read().select() {
+            if let Some (out) = tree.select() {
                 out
             } else {
+                if tree.exhausted() {
+                    self.state.lock().exhausted = true;
+                }
                 return
             };
             
         // If too deep
         if selection.get_state().queue_len() <= 2 {
+            tree.exhaust(&selection);
             return
         }
         
-        let children: Vec<_> = 'gen: {
-            let nodes = gen_children(selection.get_state());
+        let children: Vec<_> = {
+            let nodes = gen_children(selection.get_state(), &self.config);
             self.state.lock().stats.nodes += nodes.len() as u64;
 
             if nodes.len() == 0 {
-                break 'gen vec![]
+                vec![]
+            } else {
+                prune_children(nodes, &selection)
             }
-
-            let nodes = prune_children(nodes, &selection);
-
-            nodes
-                .into_iter()
-                .map(|i| Arc::new(Mutex::new(i)))
-                .collect()
         };
 
         // If no nodes, make backprop 0.
         if children.is_empty() {
             let backprop = Backprop::doomed();
-            selection.backprop(backprop);
+            tree.backprop(&selection, backprop);
+            tree.exhaust(&selection);
 
             return
         }
 
         // Add children
-        let backprop = selection.expand(children);
-        selection.backprop(backprop);
+        let Some((backprop, merged)) = tree.expand(&selection, children) else {
+            self.state.lock().full = true;
+            return
+        };
+        self.state.lock().stats.merged += merged;
+        tree.backprop(&selection, backprop);
     }
 
     pub fn work_loop (&self) {
@@ -137,6 +201,7 @@ impl Worker {
             {
                 let mut state = self.state.lock();
                 while !state.should_work() {
+                    self.idle.notify_all();
                     self.blocker.wait(&mut state);
                 }
             }
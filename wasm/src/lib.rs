@@ -56,6 +56,7 @@ pub enum Key {
     CCW  = 4,
     Drop = 5,
     Hold = 6,
+    Flip = 7,
     HardDrop = 0, // Symbolizes end of list
 }
 
@@ -125,7 +126,13 @@ impl Wrapper {
 
     #[wasm_bindgen]
     pub fn run (&mut self, ms: u32) -> Output {
-        let mov = self.bot.wasm_run(ms);
+        let mov = match self.bot.wasm_run(ms) {
+            Ok(mov) => mov,
+            Err(e) => {
+                console_log!("no move: {e}");
+                return Output::none();
+            }
+        };
         console_log!("{:?}", mov.parse_list());
         let state = self.state.clone().unwrap();
         let state = state.apply_move(&mov);
@@ -137,7 +144,9 @@ impl Wrapper {
     #[wasm_bindgen]
     pub fn advance (&mut self, input: Input) {
         self.state = Some( input.parse() );
-        self.bot.advance(&self.state.clone().unwrap());
+        if let Err(e) = self.bot.advance(&self.state.clone().unwrap()) {
+            console_log!("could not advance: {e}");
+        }
     }
 }
 
@@ -151,6 +160,7 @@ impl Output {
     pub fn from (mov: quaternion::Move) -> Self {
         let mut list: VecDeque<_> = mov
             .parse_list()
+            .unwrap_or_default()
             .iter()
             .map(|k| match k {
                 quaternion::Key::L    => Key::L,
@@ -158,7 +168,8 @@ impl Output {
                 quaternion::Key::CW   => Key::CW,
                 quaternion::Key::CCW  => Key::CCW,
                 quaternion::Key::Drop => Key::Drop,
-                quaternion::Key::Hold => Key::Hold
+                quaternion::Key::Hold => Key::Hold,
+                quaternion::Key::Flip => Key::Flip,
             })
             .collect();
         list.push_back(Key::HardDrop);
@@ -83,7 +83,7 @@ function run(state, delay) {
             if (key == Key.Right)       add("ArrowRight");
             if (key == Key.Cw)          add("ArrowUp");
             if (key == Key.Ccw)         add("z");
-            if (key == Key._180)        add("a");
+            if (key == Key.Flip)        add("a");
             if (key == Key.HardDrop)    add(" ");
             if (key == Key.SoftDrop)    add("ArrowDown");
             if (key == Key.Hold)        add("c");